@@ -27,6 +27,7 @@ use caribon::Parser;
 const ARG_LANG:&'static str = "--language=";
 const ARG_LANG_SHORT:&'static str = "-d";
 const ARG_THRESHOLD:&'static str = "--threshold=";
+const ARG_THRESHOLD_AUTO:&str = "--threshold-auto=";
 const ARG_MAX_DISTANCE:&'static str = "--max-distance=";
 const ARG_GLOBAL_THRESHOLD:&'static str = "--global-threshold=";
 const ARG_INPUT_FORMAT:&'static str = "--input-format=";
@@ -39,8 +40,16 @@ const ARG_VERSION:&'static str = "--version";
 const ARG_LIST_LANGUAGES:&'static str = "--list-languages";
 const ARG_IGNORE:&'static str = "--ignore=";
 const ARG_ADD_IGNORED:&'static str = "--add-ignored=";
+const ARG_IGNORED_FILE:&str = "--ignored-file=";
 const ARG_FUZZY:&'static str = "--fuzzy=";
+const ARG_FUZZY_MAX_DISTANCE:&str = "--fuzzy-max-distance=";
 const ARG_STATS:&'static str = "--print-stats";
+const ARG_STATS_SHORT:&str = "--stats";
+const ARG_TOP:&str = "--top=";
+const ARG_STEM:&str = "--stem=";
+const ARG_SPARKLINE:&str = "--sparkline=";
+const ARG_BOM:&str = "--bom=";
+const ARG_TRUECOLOR:&str = "--truecolor=";
 const ARG_ISPELL:&'static str = "-a";
 const ARG_ISPELL_VERSION:&'static str = "-v";
 const ARG_ISPELL_VERSION_VARIANT:&'static str = "-vv";
@@ -72,29 +81,36 @@ Options:
   {}: displays this message
   {}: displays program version
   {}: lists the implemented languages
-  {}: in addition to detecting repetition, displays some
-      statistics the input text
+  {} (or {}): in addition to detecting repetition, displays some
+      statistics on the input text
+  {}[value]: sets the number of most repeated words displayed with
+      statistics (default: 20)
   {}[language] (or {} language): sets the language of the text (default: french)
-  {}[filename]: sets input file (default: stdin)
+  {}[filename]: sets input file (default: stdin); can be given several
+      times to look for repetitions across files (e.g. chapters)
   {}[filename]: sets output file (default: stdout)
   {}[string]: sets ignored word to those contained in the string,
       separated by spaces or comma (default: the builtin list 
       that depends on the language)
   {}[string]: adds words contained in the string to the list of 
       ignored words (default: none)
+  {}[filename]: adds words contained in the file to the list of
+      ignored words; lines starting with '#' are skipped (default: none)
   {}[value]: sets max distance to be considered a repetition 
       (in words) (default: 50)
   {}[value]: sets threshold value for underlining local repetitions
       (default: 1.9)
+  {}[percentage]: chooses the threshold for local repetitions so that
+      roughly this percentage of words is underlined (default: none)
   {}[value|none]: activate global repetition detector and sets 
       threshold value for underlining global repetitions
       (this threshold corresponds to the minimal ratio of words 
       in the text, e.g. a threshold of 0.01 means that a word must
       represent at least 1% of the total words in the text to be 
       underlined) (default: not activated)
-  {}[text|html]: sets input format 
+  {}[text|html|srt|vtt|markdown]: sets input format 
       (default: text, depends on file extension)
-  {}[terminal|html]|markdown]: sets output format 
+  {}[terminal|html|markdown|text|pdf|rtf|json|xml|csv]: sets output format 
       (default: terminal, depends on file extension)
   {}[true|false]: if true, try to detect proper nouns and don't 
       count them (default: false)
@@ -103,25 +119,46 @@ Options:
       two words until they are no more considered identical (e.g. 0.25
       means that two words must have no more than 25% of difference) 
       (default: not activated)
+  {}[value]: sets the maximal edit distance between two words for
+      fuzzy string matching, regardless of their length (default: none)
+  {}[string]: prints the stem of each word contained in the string,
+      for the selected language, instead of detecting repetitions
+  {}[value]: in addition to detecting repetitions, displays a sparkline
+      of the density of repetitions, split in this number of segments
+  {}[true|false]: if true, starts the output with a UTF-8 byte order
+      mark, which some editors need to detect the encoding; not used for
+      pdf and rtf output (default: false)
+  {}[true|false]: if true, terminal output uses 24-bit colours, from
+      green to red according to how far the repetition value is above
+      the threshold (default: false)
   {}: try to run Caribon with ispell compatibility mode",
              env!("CARGO_PKG_VERSION"),
              ARG_USAGE,
              ARG_VERSION,
              ARG_LIST_LANGUAGES,
              ARG_STATS,
+             ARG_STATS_SHORT,
+             ARG_TOP,
              ARG_LANG,
              ARG_LANG_SHORT,
              ARG_INPUT,
              ARG_OUTPUT,
              ARG_IGNORE,
              ARG_ADD_IGNORED,
+             ARG_IGNORED_FILE,
              ARG_MAX_DISTANCE,
              ARG_THRESHOLD,
+             ARG_THRESHOLD_AUTO,
              ARG_GLOBAL_THRESHOLD,
              ARG_INPUT_FORMAT,
              ARG_OUTPUT_FORMAT,
              ARG_IGNORE_PROPER,
              ARG_FUZZY,
+             ARG_FUZZY_MAX_DISTANCE,
+             ARG_STEM,
+             ARG_SPARKLINE,
+             ARG_BOM,
+             ARG_TRUECOLOR,
              ARG_ISPELL
     );
 }
@@ -129,6 +166,7 @@ Options:
 pub struct Config {
     pub lang: String,
     pub threshold: f32,
+    pub threshold_auto: Option<f32>,
     pub global_threshold: Option<f32>,
     pub max_distance: u32,
     pub input_format: String,
@@ -136,12 +174,20 @@ pub struct Config {
     pub ignore_proper: bool,
     pub input: Box<Read>,
     pub input_filename: String,
+    pub other_inputs: Vec<(String, Box<Read>)>,
     pub output: Box<Write>,
     pub output_filename: String,
     pub ignored: String,
     pub add_ignored: String,
+    pub ignored_file: Option<String>,
     pub fuzzy: Option<f32>,
+    pub fuzzy_max_distance: Option<u32>,
     pub print_stats: bool,
+    pub top: usize,
+    pub stem: Option<String>,
+    pub sparkline: Option<usize>,
+    pub bom: bool,
+    pub truecolor: bool,
     pub ispell: bool,
     pub ispell_list: bool,
 }
@@ -152,6 +198,7 @@ impl Config {
         Config {
             lang: "french".to_owned(),
             threshold:1.9,
+            threshold_auto: None,
             global_threshold: None,
             max_distance:50,
             input_format: String::new(),
@@ -159,12 +206,20 @@ impl Config {
             ignore_proper:false,
             input: Box::new(io::stdin()),
             input_filename: String::new(),
+            other_inputs: vec![],
             output: Box::new(io::stdout()),
             output_filename: String::new(),
             ignored: String::new(),
             add_ignored: String::new(),
+            ignored_file: None,
             fuzzy: None,
+            fuzzy_max_distance: None,
             print_stats: false,
+            top: 20,
+            stem: None,
+            sparkline: None,
+            bom: false,
+            truecolor: false,
             ispell: false,
             ispell_list: false
         }
@@ -189,6 +244,12 @@ impl Config {
         if config.input_format.is_empty() {
             if config.input_filename.ends_with(".html") {
                 config.input_format = "html".to_owned();
+            } else if config.input_filename.ends_with(".srt") {
+                config.input_format = "srt".to_owned();
+            } else if config.input_filename.ends_with(".vtt") {
+                config.input_format = "vtt".to_owned();
+            } else if config.input_filename.ends_with(".md") {
+                config.input_format = "markdown".to_owned();
             } else {
                 config.input_format = "text".to_owned();
             }
@@ -196,8 +257,20 @@ impl Config {
         if config.output_format.is_empty() {
             if config.output_filename.ends_with(".html") {
                 config.output_format = "html".to_owned();
+            } else if config.output_filename.ends_with(".pdf") {
+                config.output_format = "pdf".to_owned();
+            } else if config.output_filename.ends_with(".rtf") {
+                config.output_format = "rtf".to_owned();
+            } else if config.output_filename.ends_with(".json") {
+                config.output_format = "json".to_owned();
+            } else if config.output_filename.ends_with(".xml") {
+                config.output_format = "xml".to_owned();
+            } else if config.output_filename.ends_with(".csv") {
+                config.output_format = "csv".to_owned();
             } else if config.output_filename.ends_with(".md") {
                 config.output_format = "markdown".to_owned();
+            } else if config.output_filename.ends_with(".txt") {
+                config.output_format = "text".to_owned();
             } else {
                 config.output_format = "terminal".to_owned();
             }
@@ -230,8 +303,12 @@ impl Config {
             let result = File::open(option);
             match result {
                 Ok(f) => {
-                    self.input = Box::new(f);
-                    self.input_filename = option.to_owned();
+                    if self.input_filename.is_empty() {
+                        self.input = Box::new(f);
+                        self.input_filename = option.to_owned();
+                    } else {
+                        self.other_inputs.push((option.to_owned(), Box::new(f)));
+                    }
                 },
                 Err(e) => {
                     println!("Error opening file {}: {}", option, e.description());
@@ -250,6 +327,14 @@ impl Config {
                     exit(0);
                 }
             }
+        } else if let Some(option) = arg.strip_prefix(ARG_THRESHOLD_AUTO) {
+            self.threshold_auto = match option.parse() {
+                Ok(x) => Some(x),
+                Err(_) => {
+                    println!("Error passing argument to threshold-auto: {}", option);
+                    exit(0);
+                }
+            }
         } else if arg.starts_with(ARG_FUZZY) {
             let option = &arg[ARG_FUZZY.len()..];
             self.fuzzy = if option == "none" {
@@ -263,6 +348,14 @@ impl Config {
                     }
                 }
             }
+        } else if let Some(option) = arg.strip_prefix(ARG_FUZZY_MAX_DISTANCE) {
+            self.fuzzy_max_distance = match option.parse() {
+                Ok(x) => Some(x),
+                Err(_) => {
+                    println!("Error passing argument to fuzzy max distance: {}", option);
+                    exit(0);
+                }
+            }
         } else if arg.starts_with(ARG_GLOBAL_THRESHOLD) {
             let option = &arg[ARG_GLOBAL_THRESHOLD.len()..];
             self.global_threshold = if option == "none" {
@@ -290,8 +383,10 @@ impl Config {
             match option {
                 "html" => self.input_format = option.to_owned(),
                 "text" => self.input_format = option.to_owned(),
+                "srt" | "vtt" => self.input_format = option.to_owned(),
+                "markdown" => self.input_format = option.to_owned(),
                 _ => {
-                    println!("Wrong argument to {}: expected 'html' or 'text', received: {}", ARG_INPUT_FORMAT, option);
+                    println!("Wrong argument to {}: expected 'html', 'text', 'srt', 'vtt' or 'markdown', received: {}", ARG_INPUT_FORMAT, option);
                     exit(0);
                 }
             }
@@ -301,8 +396,14 @@ impl Config {
                 "html" => self.output_format = option.to_owned(),
                 "terminal" => self.output_format = option.to_owned(),
                 "markdown" => self.output_format = option.to_owned(),
+                "text" => self.output_format = option.to_owned(),
+                "pdf" => self.output_format = option.to_owned(),
+                "rtf" => self.output_format = option.to_owned(),
+                "json" => self.output_format = option.to_owned(),
+                "xml" => self.output_format = option.to_owned(),
+                "csv" => self.output_format = option.to_owned(),
                 _ => {
-                    println!("Wrong argument to {}: expected 'html', 'terminal' or 'markdown', received: {}", ARG_OUTPUT_FORMAT, option);
+                    println!("Wrong argument to {}: expected 'html', 'terminal', 'markdown', 'text', 'pdf', 'rtf', 'json', 'xml' or 'csv', received: {}", ARG_OUTPUT_FORMAT, option);
                     exit(0);
                 }
             }
@@ -322,6 +423,36 @@ impl Config {
         } else if arg.starts_with(ARG_ADD_IGNORED) {
             let option = &arg[ARG_ADD_IGNORED.len()..];
             self.add_ignored = option.to_owned();
+        } else if let Some(option) = arg.strip_prefix(ARG_IGNORED_FILE) {
+            self.ignored_file = Some(option.to_owned());
+        } else if let Some(option) = arg.strip_prefix(ARG_STEM) {
+            self.stem = Some(option.to_owned());
+        } else if let Some(option) = arg.strip_prefix(ARG_BOM) {
+            match option {
+                "true" => self.bom = true,
+                "false" => self.bom = false,
+                _ => {
+                    println!("Wrong argument to bom: expected 'true' or 'false', received: {}", option);
+                    exit(0);
+                }
+            }
+        } else if let Some(option) = arg.strip_prefix(ARG_TRUECOLOR) {
+            match option {
+                "true" => self.truecolor = true,
+                "false" => self.truecolor = false,
+                _ => {
+                    println!("Wrong argument to truecolor: expected 'true' or 'false', received: {}", option);
+                    exit(0);
+                }
+            }
+        } else if let Some(option) = arg.strip_prefix(ARG_SPARKLINE) {
+            self.sparkline = match option.parse() {
+                Ok(x) => Some(x),
+                Err(_) => {
+                    println!("Error passing argument to sparkline: {}", option);
+                    exit(0);
+                }
+            }
         } else if arg == ARG_USAGE {
             usage();
             exit(0);
@@ -331,7 +462,15 @@ impl Config {
         } else if arg == ARG_LIST_LANGUAGES {
             list_languages();
             exit(0);
-        } else if arg == ARG_STATS {
+        } else if let Some(option) = arg.strip_prefix(ARG_TOP) {
+            self.top = match option.parse() {
+                Ok(x) => x,
+                Err(_) => {
+                    println!("Error passing argument to top: {}", option);
+                    exit(0);
+                }
+            }
+        } else if arg == ARG_STATS || arg == ARG_STATS_SHORT {
             self.print_stats = true;
         } else if arg == ARG_ISPELL {
             self.ispell = true;
@@ -25,12 +25,20 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::io::BufRead;
-use std::collections::HashMap;
+use std::path::Path;
 
-fn print_stats(h: &HashMap<String, f32>, n_words: u32) {
-    let different_words = h.len();
-    println!("Number of words: {}", n_words);
-    println!("Number of different words: {}", different_words);
+fn print_stats(stats: &caribon::Stats, top: usize) {
+    println!("Number of words: {}", stats.total_words);
+    println!("Number of different words: {}", stats.unique_stems);
+    if top > 0 && !stats.top.is_empty() {
+        println!("Most repeated words:");
+        for &(ref stem, count) in stats.top.iter().take(top) {
+            println!("  {}: {} ({:.2}%)",
+                     stem,
+                     count,
+                     100.0 * count as f32 / stats.total_words as f32);
+        }
+    }
 }
 
 fn try_parse() -> Result<(), Box<Error>> {
@@ -38,37 +46,90 @@ fn try_parse() -> Result<(), Box<Error>> {
     let mut parser = try!(Parser::new(&config.lang));
 
     parser = parser.with_html(&config.input_format == "html")
+                   .with_subtitles(&config.input_format == "srt" || &config.input_format == "vtt")
+                   .with_markdown(&config.input_format == "markdown")
                    .with_fuzzy(config.fuzzy)
                    .with_ignore_proper(config.ignore_proper)
                    .with_max_distance(config.max_distance);
 
+    if let Some(max_distance) = config.fuzzy_max_distance {
+        parser = parser.with_fuzzy_max_distance(max_distance);
+    }
     if !config.ignored.is_empty() {
         parser = parser.with_ignored(&config.ignored);
     }
     if !config.add_ignored.is_empty() {
         parser = parser.with_more_ignored(&config.add_ignored);
     }
+    if let Some(ref path) = config.ignored_file {
+        parser = try!(parser.with_ignored_from_file(Path::new(path)));
+    }
+    if let Some(ref words) = config.stem {
+        parser = parser.with_html(false);
+        let ast = try!(parser.tokenize(words));
+        for token in parser.tokens(&ast) {
+            match token.kind {
+                caribon::WordKind::Tracked => println!("{}: {}", token.text, token.stem.unwrap()),
+                caribon::WordKind::Ignored => println!("{}: (ignored)", token.text),
+                caribon::WordKind::Untracked => (),
+            }
+        }
+        return Ok(());
+    }
     if !config.ispell {
         let mut s = String::new();
         try!(config.input.read_to_string(&mut s));
-        
-        let mut ast = try!(parser.tokenize(&s));
+        let mut sources = vec![(config.input_filename.clone(), s)];
+        for &mut (ref name, ref mut input) in &mut config.other_inputs {
+            let mut s = String::new();
+            try!(input.read_to_string(&mut s));
+            sources.push((name.clone(), s));
+        }
+
+        for (_, s) in &sources {
+            if let Some(warning) = try!(parser.fuzzy_warning(s)) {
+                try!(writeln!(io::stderr(), "Warning: {}", warning));
+            }
+        }
+        let mut ast = if sources.len() == 1 {
+            try!(parser.tokenize(&sources[0].1))
+        } else {
+            try!(parser.tokenize_many(&sources))
+        };
         if config.print_stats {
-        let (h, count) = parser.words_stats(&ast);
-            print_stats(&h, count);
+            print_stats(&parser.stats_report(&ast), config.top);
+        }
+        match config.threshold_auto {
+            Some(percentile) => {
+                parser.detect_local_percentile(&mut ast, percentile);
+            }
+            None => parser.detect_local(&mut ast, config.threshold),
         }
-        parser.detect_local(&mut ast, config.threshold);
         if let Some(threshold) = config.global_threshold {
             parser.detect_global(&mut ast, threshold);
         }
+        if let Some(buckets) = config.sparkline {
+            println!("{}", parser.density_sparkline(&ast, buckets));
+        }
         let output = match &*config.output_format {
-            "html" => parser.ast_to_html(&mut ast, true),
-            "terminal" => parser.ast_to_terminal(&ast),
-            "markdown" => parser.ast_to_markdown(&ast),
+            "html" => parser.ast_to_html(&mut ast, true).into_bytes(),
+            "terminal" if config.truecolor => parser.ast_to_terminal_truecolor(&ast).into_bytes(),
+            "terminal" => parser.ast_to_terminal(&ast).into_bytes(),
+            "markdown" => parser.ast_to_markdown(&ast).into_bytes(),
+            "text" => parser.ast_to_text(&ast).into_bytes(),
+            "pdf" => parser.ast_to_pdf(&ast),
+            "rtf" => parser.ast_to_rtf(&ast).into_bytes(),
+            "json" => parser.ast_to_json(&ast).into_bytes(),
+            "xml" => parser.ast_to_xml(&ast).into_bytes(),
+            "csv" => parser.ast_to_csv(&ast).into_bytes(),
             _ => return Err(Box::new(caribon::Error::new("Wrong output format: must be 'html, \
-                                                          'terminal', or 'markdown'"))),
+                                                          'terminal', 'markdown', 'text', 'pdf', \
+                                                          'rtf', 'json', 'xml' or 'csv'"))),
         };
-        try!(config.output.write(&output.bytes().collect::<Vec<u8>>()));
+        if config.bom && &config.output_format != "pdf" && &config.output_format != "rtf" {
+            try!(config.output.write(b"\xEF\xBB\xBF"));
+        }
+        try!(config.output.write(&output));
         Ok(())
     } else {
         config::disguise_as_ispell();
@@ -111,6 +172,6 @@ fn try_parse() -> Result<(), Box<Error>> {
 fn main() {
     match try_parse() {
         Ok(_) => {}
-        Err(e) => println!("{}", e.description()),
+        Err(e) => println!("{}", e),
     }
 }
@@ -17,6 +17,8 @@
 
 // Code to end shell colouring
 pub const SHELL_COLOUR_OFF: &'static str = "\x1B[0m";
+// Code to write in italics in the shell
+pub const SHELL_ITALIC: &str = "\x1B[3m";
 
 // Javascript function to print repetitions
 pub const SCRIPTS: &'static str = include_str!("html/scripts.js");
@@ -28,22 +30,92 @@ pub fn get_shell_colour(colour: &str) -> Option<&'static str> {
         "green" => Some("\x1B[4;32m"),
         "cyan" => Some("\x1B[4;36m"),
         "brown" => Some("\x1B[4;33m"),
-        "blue" => Some("\x1B[4;32m"),
+        "blue" => Some("\x1B[4;34m"),
         "purple" => Some("\x1B[4;35m"),
         "orange" => Some("\x1B[4;33m"),
         _ => None,
     }
 }
 
-/// Generate the style attribute according to x and threshold
-pub fn value_to_colour(x: f32, threshold: f32) -> &'static str {
-    if x < threshold {
-        panic!("value_to_colour called with x < threshold");
-    } else if x < 1.5 * threshold {
-        "green"
-    } else if x < 2.0 * threshold {
-        "orange"
+/// Get the red and green components of a colour on a gradient from green (`t` = 0) to
+/// red (`t` = 1), through yellow
+pub fn get_gradient_colour(t: f32) -> (u8, u8) {
+    let t = t.clamp(0.0, 1.0);
+    let r = (255.0 * (2.0 * t).min(1.0)).round() as u8;
+    let g = (255.0 * (2.0 * (1.0 - t)).min(1.0)).round() as u8;
+    (r, g)
+}
+
+/// Get a 24-bit shell colour on a gradient from green (`t` = 0) to red (`t` = 1), through yellow
+pub fn get_truecolor_shell_colour(t: f32) -> String {
+    let (r, g) = get_gradient_colour(t);
+    format!("\x1B[4m\x1B[38;2;{};{};0m", r, g)
+}
+
+/// Default colour scale used by `value_to_colour`: multiples of the threshold above which
+/// each colour is used
+pub const DEFAULT_COLOUR_SCALE: &[(f32, &str)] = &[(1.0, "green"),
+                                                  (1.5, "orange"),
+                                                  (2.0, "red")];
+
+/// Generate the style attribute according to x and threshold, picking a colour in the scale
+///
+/// The scale must be sorted by increasing multiple; if x is below all of them, the first
+/// colour is used.
+pub fn value_to_colour(x: f32, threshold: f32, scale: &[(f32, String)]) -> String {
+    scale.iter()
+         .take_while(|&&(m, _)| x >= m * threshold)
+         .last()
+         .unwrap_or(&scale[0])
+         .1
+         .clone()
+}
+
+/// Escape a string for XML output (both for text and attribute values)
+pub fn xml_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&apos;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Escape a field for CSV output, quoting it if necessary
+pub fn csv_escape(s: &str) -> String {
+    if s.contains(',') || s.contains('"') || s.contains('\n') {
+        format!("\"{}\"", s.replace("\"", "\"\""))
     } else {
-        "red"
+        s.to_owned()
+    }
+}
+
+/// Escape a string for RTF output
+///
+/// Non-ASCII characters are written as unicode escapes, with '?' as fallback.
+pub fn rtf_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '{' => res.push_str("\\{"),
+            '}' => res.push_str("\\}"),
+            '\n' => res.push_str("\\par\n"),
+            '\r' => (),
+            c if (c as u32) < 0x80 => res.push(c),
+            c => {
+                let mut buffer = [0; 2];
+                for unit in c.encode_utf16(&mut buffer) {
+                    res.push_str(&format!("\\u{}?", *unit as i16));
+                }
+            }
+        }
     }
+    res
 }
@@ -0,0 +1,36 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::strsim::levenshtein;
+
+/// Returns the edit (Levenshtein) distance between two strings.
+///
+/// This is the minimal number of insertions, deletions or substitutions of characters needed
+/// to change one string into the other. Distances are counted in characters, not bytes, so
+/// accented letters count as one character. This is the distance used for fuzzy matching
+/// (see `Parser::with_fuzzy`).
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(caribon::edit_distance("créé", "cree"), 2);
+/// assert_eq!(caribon::edit_distance("créé", "crée"), 1);
+/// assert_eq!(caribon::edit_distance("", "été"), 3);
+/// ```
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    levenshtein(a, b)
+}
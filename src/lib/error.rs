@@ -20,20 +20,64 @@ use std::result;
 use std::fmt;
 
 #[derive(Debug)]
-/// Caribon error type (just a String currently)
+/// Caribon error type
+///
+/// # Example
+///
+/// ```rust
+/// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+/// let err = parser.tokenize("<p>Some text <b").unwrap_err();
+/// assert_eq!(err.position(), Some(13));
+/// assert!(err.to_string().contains("at character 13"));
+/// assert!(err.to_string().contains("Some text <b"));
+///
+/// let err = parser.tokenize("Fish &amp chips").unwrap_err();
+/// assert_eq!(err.position(), Some(5));
+///
+/// let err = caribon::Error::with_position("Unexpected '<'.", 4);
+/// assert_eq!(err.content(), "Unexpected '<'.");
+/// assert_eq!(err.to_string(), "Unexpected '<'. (at character 4)");
+/// ```
 pub struct Error {
-    pub content: String,
+    pub(crate) content: String,
+    /// Position (in characters) in the input where the error occured, if known
+    pub(crate) position: Option<usize>,
 }
 
 impl Error {
     pub fn new(s: &str) -> Error {
-        Error { content: s.to_owned() }
+        Error {
+            content: s.to_owned(),
+            position: None,
+        }
+    }
+
+    /// Creates an error occuring at the given position (in characters) in the input
+    pub fn with_position(s: &str, position: usize) -> Error {
+        Error {
+            content: s.to_owned(),
+            position: Some(position),
+        }
+    }
+
+    /// Returns the error message, without the position
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Returns the position (in characters) in the input where the error occured, if known
+    pub fn position(&self) -> Option<usize> {
+        self.position
     }
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(&self.content)
+        try!(f.write_str(&self.content));
+        if let Some(position) = self.position {
+            try!(write!(f, " (at character {})", position));
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,46 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+const EXAMPLES: &[(&str, &str)] = &[
+    ("english", include_str!("examples/english.txt")),
+    ("french", include_str!("examples/french.txt"))];
+
+/// Returns some example texts, containing repetitions.
+///
+/// Each example is a tuple containing the language of the text (which can be given
+/// to `Parser::new`) and the text itself.
+///
+/// # Example
+///
+/// ```rust
+/// use caribon::Parser;
+/// for &(lang, text) in caribon::examples() {
+///     let mut parser = Parser::new(lang).unwrap();
+///     let mut ast = parser.tokenize(text).unwrap();
+///     parser.detect_local(&mut ast, 1.9);
+///     assert!(!parser.ast_to_repetitions(&ast).is_empty());
+/// }
+///
+/// let (_, text) = caribon::examples()[0];
+/// let mut parser = Parser::new("english").unwrap();
+/// let mut ast = parser.tokenize(text).unwrap();
+/// parser.detect_local(&mut ast, 1.9);
+/// assert!(parser.ast_to_markdown(&ast).contains("the **garden**"));
+/// ```
+pub fn examples() -> &'static [(&'static str, &'static str)] {
+    EXAMPLES
+}
@@ -0,0 +1,64 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::hash::Hasher;
+
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a hasher.
+///
+/// Unlike the standard library's default hasher, its output is guaranteed to stay the same
+/// between Rust versions and platforms, so it can be used for fingerprints that are stored.
+pub struct FnvHasher(u64);
+
+impl FnvHasher {
+    pub fn new() -> FnvHasher {
+        FnvHasher(FNV_OFFSET)
+    }
+}
+
+impl Hasher for FnvHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // Integers are always written in little endian, and usize as u64, so the result
+    // does not depend on the platform
+    fn write_u32(&mut self, n: u32) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        let mut bytes = [0; 8];
+        for (i, b) in bytes.iter_mut().enumerate() {
+            *b = (n >> (8 * i)) as u8;
+        }
+        self.write(&bytes);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+}
@@ -26,10 +26,13 @@
 //! ```
 //! use caribon::Parser;
 //! let mut parser = Parser::new("english").unwrap(); //creates a new parser
-//! let mut ast = parser.tokenize("Some text where you want to detect repetitions").unwrap();
+//! let mut ast = parser.tokenize("Some text where you want to detect repetitions in a text").unwrap();
 //! parser.detect_local(&mut ast, 1.5);
 //! parser.detect_global(&mut ast, 0.01); // wouldn't actually make much sense on a string so small
 //! let html = parser.ast_to_html(&mut ast, true);
+//! assert!(html.starts_with("<html>"));
+//! assert_eq!(html.matches("<span class = \"text\"").count(), 2);
+//! assert!(html.contains("style = \"text-decoration: underline; color: green;\">text</span>"));
 //! println!("{}", html);
 //! ```
 //!
@@ -90,7 +93,7 @@
 //! let html = parser.ast_to_html(&mut ast, true);
 //! ```
 //!
-//! There are two other "outputting" methods: `ast_to_terminal` and `ast_to_markdown`:
+//! There are other "outputting" methods, e.g. `ast_to_terminal` and `ast_to_markdown`:
 //!
 //! ```ignore
 //! let output_terminal = parser.ast_to_terminal(&ast);
@@ -108,11 +111,20 @@
 
 extern crate stemmer;
 extern crate strsim;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
 
 mod error;
 mod word;
 mod parser;
+mod streaming;
 mod display;
+mod distance;
+mod hash;
+mod pdf;
+mod examples;
 
 
 pub use error::Error;
@@ -121,3 +133,17 @@ pub use word::Word;
 pub use word::Ast;
 pub use parser::Parser;
 pub use parser::Repetition;
+pub use parser::RepetitionGroup;
+pub use parser::Stats;
+pub use parser::TokenInfo;
+pub use parser::WordKind;
+pub use parser::Spelling;
+pub use parser::ConfigSnapshot;
+pub use parser::DistanceUnit;
+pub use parser::ProperNounMode;
+pub use parser::Capabilities;
+pub use parser::LanguageInfo;
+pub use streaming::StreamingDetector;
+pub use streaming::StreamEvent;
+pub use examples::examples;
+pub use distance::edit_distance;
@@ -16,18 +16,178 @@
 // along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::stemmer::Stemmer;
-use super::strsim::levenshtein;
+use super::serde_json;
 
 use word::{Word, Ast};
 use error::{Error, Result};
-use std::collections::HashMap;
-use display::{get_shell_colour, value_to_colour, SHELL_COLOUR_OFF, SCRIPTS};
+use hash::FnvHasher;
+use pdf::PdfWriter;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use distance::edit_distance;
+use display::{get_shell_colour, get_truecolor_shell_colour, value_to_colour, csv_escape, xml_escape,
+              rtf_escape, get_gradient_colour, SHELL_COLOUR_OFF, SHELL_ITALIC, SCRIPTS, DEFAULT_COLOUR_SCALE};
 
-type TokenizeResult<'a> = Result<(&'a [char], Word)>;
+/// Kind of a token, whose text is then taken from the input by `Parser::tokenize_chunk`
+enum Token {
+    Untracked,
+    Ignored,
+    /// A tracked word, with its stemmed variant
+    Tracked(String),
+}
+
+type TokenizeResult<'a> = Result<(&'a [char], Token)>;
+
+/// State of the tokenizer between two chunks of a text (see `Parser::tokenize_reader`)
+struct TokenizerState<'a> {
+    ast: Ast<'a>,
+    /// Text the words borrow, if any (see `Parser::tokenize_borrowed`), and the position in
+    /// bytes of the next token in it
+    source: Option<&'a str>,
+    byte: usize,
+    is_sentence_beginning: bool,
+    in_body: bool,
+    /// Depth of nested <pre>, <code>, <script> or <style> elements
+    verbatim: u32,
+    /// Ranges of headings, and of characters that are not part of the text (subtitle headers
+    /// or Markdown code), sorted
+    headings: Vec<(usize, usize)>,
+    untracked: Vec<(usize, usize)>,
+    heading_cursor: usize,
+    untracked_cursor: usize,
+    /// Indexes of words ignored as proper nouns, and lowercase forms of tracked words
+    proper_nouns: Vec<usize>,
+    lowercase_forms: HashSet<String>,
+}
+
+/// Characters that are read but not tokenized yet, preceded by the last tokenized ones (at most
+/// ERROR_CONTEXT), which are still needed to tokenize the following ones
+struct CharBuffer {
+    chars: Vec<char>,
+    /// Number of tokenized characters at the beginning of chars
+    start: usize,
+    /// Position in the input of the first character of chars
+    offset: usize,
+}
+
+/// A closure returning alternatives to a word, see `Parser::with_synonyms`
+type SynonymProvider = Box<Fn(&str) -> Vec<String> + Send + Sync>;
 
 const IGNORED_FR: &'static str = "la le les pas ne nos des de du ils elles il elle se on nous vous leur \
                                   leurs et un une t s à d l je tu en";
 const IGNORED_EN: &'static str = "it s i of the a you we she he they them its their";
+const IGNORED_DA: &str = "og i at det som en et den de på er af for med til jeg du han hun vi \
+                          sig sin hans ikke om";
+const IGNORED_NO: &str = "og i å det som en et ei den de på er av for med til jeg du han hun \
+                          vi dere seg sin hans ikke om";
+const IGNORED_SV: &str = "och i att det som en ett den de på är av för med till jag du han \
+                          hon vi ni sig sin hans inte om";
+
+// Abbreviations whose final dot doesn't end a sentence
+const ABBREVIATIONS_EN: &str = "mr mrs ms dr prof st jr sr etc vs cf";
+const ABBREVIATIONS_FR: &str = "m mm mme mmes mlle mlles dr pr st ste etc cf";
+
+// Common words, from the most frequent to the least frequent (see `detect_local_weighted`)
+const COMMON_EN: &str = "be have do say get make go know take see come think look want give \
+                         use find tell ask work seem feel try leave call time people way year \
+                         day man thing woman life child world school state family student group \
+                         country problem hand part place case week company system program \
+                         question government number night point home water room mother area \
+                         money story fact month lot right study book eye job word business \
+                         issue side kind head house service friend father power hour game line \
+                         end member law car city name team minute idea body information back \
+                         parent face level office door health person art war history party \
+                         result change morning reason moment air teacher force";
+const COMMON_FR: &str = "être avoir faire dire aller voir savoir pouvoir vouloir venir falloir \
+                         devoir prendre trouver donner parler mettre passer regarder aimer \
+                         croire demander rester répondre entendre penser arriver connaître \
+                         devenir sentir sembler tenir comprendre rendre attendre sortir vivre \
+                         temps homme jour fois chose main vie monde femme an moment enfant \
+                         pays année nuit heure tête maison père mère ami place point côté porte \
+                         oeil yeux mot travail voix air fille fils nom coeur question guerre \
+                         ville terre gens idée histoire matin soir raison famille chambre";
+// Characters that are part of a word when they are between two letters, for some languages
+const WORD_CHARS_CA: &str = "·";
+const WORD_CHARS_BR: &str = "'’";
+// Languages that the stemming library doesn't support, but which can be used for their word
+// characters, without stemming
+const UNSTEMMED_LANGUAGES: &[&str] = &["catalan", "breton"];
+// Languages for which words only contain letters (besides those in WORD_CHARS_*)
+const LETTERS_ONLY: &[&str] = &["en", "fr", "da", "no", "sv"];
+
+// Number of characters after a token that must be known before tokenizing it, when the
+// text is read in chunks (see `tokenize_reader`)
+const TOKEN_LOOKAHEAD: usize = 256;
+// Number of characters before and after an error that are given in its message
+const ERROR_CONTEXT: usize = 20;
+
+// Number of characters stems are truncated to with aggressive stemming
+const AGGRESSIVE_STEM_LENGTH: usize = 5;
+// Languages for which aggressive stemming is available
+const AGGRESSIVE_LANGUAGES: &[&str] = &["fi", "hu"];
+
+// Colour used to highlight words written in capitals
+const ALLCAPS_COLOUR: &str = "purple";
+
+// Minimal number of consecutive clauses for a repetition to be considered as deliberate
+const RHETORICAL_MIN_CLAUSES: usize = 3;
+
+// Beginning of words that are spelled differently in british and american english
+const SPELLING_VARIANTS: &[(&str, &str)] = &[
+    ("colour", "color"), ("honour", "honor"), ("favour", "favor"), ("labour", "labor"),
+    ("neighbour", "neighbor"), ("behaviour", "behavior"), ("humour", "humor"),
+    ("flavour", "flavor"), ("organis", "organiz"), ("realis", "realiz"),
+    ("recognis", "recogniz"), ("apologis", "apologiz"), ("centre", "center"),
+    ("theatre", "theater"), ("litre", "liter"), ("defence", "defense"),
+    ("offence", "offense"), ("grey", "gray"), ("catalogue", "catalog")];
+
+/// English spelling variant, used for spelling normalization
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Spelling {
+    /// British spelling (e.g. "colour", "organise")
+    British,
+    /// American spelling (e.g. "color", "organize")
+    American,
+}
+
+/// Unit used to measure the distance between two occurrences of a word
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DistanceUnit {
+    /// Distance is the number of words (the default)
+    Words,
+    /// Distance is the number of characters
+    Characters,
+}
+
+/// How capitalized words are considered as proper nouns, see `Parser::with_proper_noun_mode`
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ProperNounMode {
+    /// Capitalized words are not treated differently (the default)
+    Off,
+    /// Capitalized words (including acronyms) are ignored
+    IgnoreCapitalized,
+    /// Capitalized words are ignored, except acronyms such as "NASA" which are still counted
+    IgnoreCapitalizedExceptAcronyms,
+}
+
+/// Statistics about the words of a text, as returned by `Parser::stats_report`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Number of words (tracked or ignored) of the text
+    pub total_words: u32,
+    /// Number of different stems of tracked words
+    pub unique_stems: usize,
+    /// Stems of tracked words and their number of occurrences, from the most frequent to
+    /// the least frequent
+    pub top: Vec<(String, u32)>,
+}
 
 /// A detected repetition
 #[derive(Debug)]
@@ -39,33 +199,302 @@ pub struct Repetition {
     /// The "severity" of the repetition
     pub value: f32,
     /// How the repetition should be displayed
-    pub colour: &'static str,
+    pub colour: String,
+    nearest: Option<u32>,
+}
+
+impl Repetition {
+    /// Returns the distance (in words) to the nearest other occurrence of the same word, if any
+    pub fn nearest(&self) -> Option<u32> {
+        self.nearest
+    }
+}
+
+/// The occurrences of a word that is used several times, as returned by `Parser::repetitions`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepetitionGroup {
+    /// The stemmed word
+    pub stemmed: String,
+    /// The positions of the occurrences (indexes in `ast.words`)
+    pub positions: Vec<usize>,
+    /// For each occurrence, the distance (in words) to the nearest other occurrence
+    pub distances: Vec<u32>,
+    /// The highest repetition value of the occurrences
+    pub value: f32,
+    /// Whether at least one of the occurrences is highlighted
+    pub highlighted: bool,
+}
+
+/// The kind of a `Word`, without its content
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WordKind {
+    /// See `Word::Untracked`
+    Untracked,
+    /// See `Word::Ignored`
+    Ignored,
+    /// See `Word::Tracked`
+    Tracked,
+}
+
+/// A word, as serialized by `Parser::ast_to_json`
+#[derive(Serialize)]
+struct JsonWord<'a> {
+    text: &'a str,
+    kind: WordKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stemmed: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    colour: Option<&'a str>,
+}
+
+/// Flat information about a token, as returned by `Parser::tokens`
+#[derive(Debug, Clone)]
+pub struct TokenInfo {
+    /// The text of the token, as it appears in the input
+    pub text: String,
+    /// The kind of the token
+    pub kind: WordKind,
+    /// The stemmed word, for tracked words
+    pub stem: Option<String>,
+    /// The highlighting colour, if any
+    pub colour: Option<String>,
+}
+
+/// The features that are supported for a language, as returned by `Parser::capabilities`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capabilities {
+    /// Whether words can be stemmed
+    pub stemmer: bool,
+    /// Whether there is a builtin list of ignored words (see `Parser::get_ignored_from_lang`)
+    pub stopwords: bool,
+    /// Whether there is a builtin list of common words (used by `Parser::detect_local_weighted`)
+    pub frequency_list: bool,
+    /// Whether sentences are known to be split correctly for this language, i.e. there is a
+    /// builtin list of abbreviations whose dot doesn't end a sentence (see
+    /// `Parser::with_abbreviations`)
+    pub sentence_terminators: bool,
+    /// Whether the characters that are part of a word are known for this language (see
+    /// `Parser::get_word_chars_from_lang`)
+    pub word_chars: bool,
+}
+
+/// Information about a language that can be given to `Parser::new`, as returned by
+/// `Parser::language_info`
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageInfo {
+    /// The name of the language
+    pub name: &'static str,
+    /// Whether there is a builtin list of ignored words (see `Parser::get_ignored_from_lang`)
+    pub has_ignore_defaults: bool,
+    /// Whether words can be stemmed
+    pub stemmer_available: bool,
+}
+
+/// The configuration of a `Parser`, as returned by `Parser::export_config`
+///
+/// It can be serialized, and used to create a parser with the same configuration with
+/// `Parser::from_config`. See the corresponding methods of `Parser` for the meaning of each field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigSnapshot {
+    /// See `Parser::new`
+    pub lang: String,
+    /// See `Parser::with_ignored`
+    pub ignored: Vec<String>,
+    /// See `Parser::with_ignored_phrases`
+    pub ignored_phrases: Vec<Vec<String>>,
+    /// See `Parser::with_adaptive_stoplist`
+    pub adaptive_stoplist: Option<f32>,
+    /// See `Parser::get_word_chars_from_lang` and `Parser::with_word_chars`
+    pub word_chars: Vec<char>,
+    /// See `Parser::with_html`
+    pub html: bool,
+    /// See `Parser::with_proper_noun_mode`
+    pub proper_noun_mode: ProperNounMode,
+    /// See `Parser::with_case_sensitive`
+    pub case_sensitive: bool,
+    /// See `Parser::with_max_distance`
+    pub max_distance: u32,
+    /// See `Parser::with_leak`
+    pub leak: f32,
+    /// See `Parser::with_distance_unit`
+    pub distance_unit: DistanceUnit,
+    /// See `Parser::with_fuzzy`
+    pub fuzzy: Option<f32>,
+    /// See `Parser::with_fuzzy_max_distance`
+    pub fuzzy_max_distance: Option<u32>,
+    /// See `Parser::with_max_sentence_distance`
+    pub max_sentence_distance: Option<u32>,
+    /// See `Parser::with_min_occurrences`
+    pub min_occurrences: u32,
+    /// See `Parser::with_max_highlights`
+    pub max_highlights: Option<usize>,
+    /// See `Parser::with_colour_scale`
+    pub colour_scale: Vec<(f32, String)>,
+    /// See `Parser::with_colour_definition`
+    pub colour_definitions: HashMap<String, (String, String)>,
+    /// See `Parser::with_html_tooltips`
+    pub html_tooltips: bool,
+    /// See `Parser::with_mark_fuzzy`
+    pub mark_fuzzy: bool,
+    /// See `Parser::with_highlight_markers`
+    pub highlight_markers: (String, String),
+    /// See `Parser::with_collapse_highlights`
+    pub collapse_highlights: bool,
+    /// See `Parser::with_paragraph_density`
+    pub paragraph_density: bool,
+    /// See `Parser::with_lowercase_classes`
+    pub lowercase_classes: bool,
+    /// See `Parser::with_margins`
+    pub margins: (usize, usize),
+    /// See `Parser::with_ignore_headings`
+    pub ignore_headings: bool,
+    /// See `Parser::with_ignore_table_headers`
+    pub ignore_table_headers: bool,
+    /// See `Parser::with_single_sentence`
+    pub single_sentence: bool,
+    /// See `Parser::with_sentence_terminators`
+    pub sentence_terminators: Vec<char>,
+    /// See `Parser::with_abbreviations`
+    pub abbreviations: Vec<String>,
+    /// See `Parser::with_ignore_enumerations`
+    pub ignore_enumerations: bool,
+    /// See `Parser::with_subtitles`
+    pub subtitles: bool,
+    /// See `Parser::with_markdown`
+    pub markdown: bool,
+    /// See `Parser::with_dehyphenate`
+    pub dehyphenate: bool,
+    /// See `Parser::with_technical_tokens`
+    pub technical_tokens: Option<WordKind>,
+    /// See `Parser::with_track_numbers`
+    pub track_numbers: bool,
+    /// See `Parser::with_html_summary`
+    pub html_summary: Option<usize>,
+    /// See `Parser::with_spelling_normalization`
+    pub spelling: Option<Spelling>,
+    /// See `Parser::with_stemming`
+    pub stemming: bool,
+    /// See `Parser::with_aggressive_stemming`
+    pub aggressive_stemming: bool,
+    /// See `Parser::with_only_replaceable`
+    pub only_replaceable: bool,
+    /// Stems of words belonging to a synonym group -> key of the group (see
+    /// `Parser::with_synonym_groups`)
+    pub synonym_groups: HashMap<String, String>,
 }
 
 /// Parser which can load a string, detects repetition on it and outputs an HTML file.
 #[repr(C)]
 pub struct Parser {
+    /// The language given to `new`
+    lang: String,
     /// The stemmer 
     stemmer: Option<Stemmer>,
     /// List of ignored words: we don't want to count repetitions on them
     ignored: Vec<String>,
+    /// Phrases (as lists of lowercase words) whose words are ignored when they are together
+    ignored_phrases: Vec<Vec<String>>,
+    /// Percentage of the most frequent stems of a text that are also ignored
+    adaptive_stoplist: Option<f32>,
+    /// Non-alphabetic characters that are part of a word when they are between two letters
+    word_chars: Vec<char>,
     /// Whether there is HTML in the input text
     html: bool,
-    /// Ignores proper nouns
-    ignore_proper: bool,
+    /// Whether (and which) proper nouns are ignored
+    proper_noun_mode: ProperNounMode,
+    /// Whether words that only differ by case are considered different
+    case_sensitive: bool,
     /// Max distance to consider a repetition, only used for detect_local
     max_distance: u32,
+    /// Unit of max_distance
+    distance_unit: DistanceUnit,
+    /// Factor by which the contribution of an occurrence decays at each word, only used
+    /// for detect_leak
+    leak: f32,
     /// Triggers fuzzy string matching
     fuzzy: Option<f32>,
+    /// Max (absolute) edit distance for fuzzy string matching
+    fuzzy_max_distance: Option<u32>,
+    /// Max distance (in sentences) to consider a repetition, only used for detect_local
+    max_sentence_distance: Option<u32>,
+    /// Min number of occurrences in the whole text for a word to be highlighted, only used for
+    /// detect_local
+    min_occurrences: u32,
+    /// Max number of words highlighted by each detection pass
+    max_highlights: Option<usize>,
+    /// Colours used by detect_local, with the multiple of the threshold above which they are
+    /// used, from the lowest to the highest repetition value
+    colour_scale: Vec<(f32, String)>,
+    /// User-defined colours: name -> (terminal escape code, HTML colour)
+    colour_definitions: HashMap<String, (String, String)>,
+    /// Whether HTML output shows the repetition value in a tooltip
+    html_tooltips: bool,
+    /// Whether HTML and terminal outputs show words merged by fuzzy matching differently
+    mark_fuzzy: bool,
+    /// Strings around highlighted words in text output
+    highlight_markers: (String, String),
+    /// Whether HTML output collapses consecutive highlighted words into a single highlight
+    collapse_highlights: bool,
+    /// Whether HTML output annotates paragraphs with their density of repetitions
+    paragraph_density: bool,
+    /// Whether the CSS classes of HTML output are lowercased
+    lowercase_classes: bool,
+    /// Number of words to ignore at the beginning and at the end of the text
+    margins: (usize, usize),
+    /// Ignores words in (markdown) headings, in text mode
+    ignore_headings: bool,
+    /// Ignores words in the header rows of tables, in text mode
+    ignore_table_headers: bool,
+    /// Treats the whole text as a single sentence (for proper nouns detection)
+    single_sentence: bool,
+    /// Characters that end a sentence
+    sentence_terminators: Vec<char>,
+    /// Words (in lowercase) that are followed by a dot without ending a sentence
+    abbreviations: Vec<String>,
+    /// Doesn't count repetitions inside enumerations
+    ignore_enumerations: bool,
+    /// Whether the input is a subtitle file (SRT or WebVTT)
+    subtitles: bool,
+    /// Whether the input is in Markdown
+    markdown: bool,
+    /// Rejoins words split by a hyphen at the end of a line
+    dehyphenate: bool,
+    /// Kind of the words for version numbers, dates and identifiers, if they are kept whole
+    technical_tokens: Option<WordKind>,
+    /// Whether numbers are tracked
+    track_numbers: bool,
+    /// Number of words to list in the summary panel of standalone HTML output
+    html_summary: Option<usize>,
+    /// Normalizes english words to this spelling before stemming
+    spelling: Option<Spelling>,
+    /// If false, words are not stemmed and only exact forms are counted as repetitions
+    stemming: bool,
+    /// Truncates stems, for agglutinative languages
+    aggressive_stemming: bool,
+    /// Returns alternatives for a (lowercase) word
+    synonyms: Option<SynonymProvider>,
+    /// Only highlights words for which the synonym provider returns alternatives
+    only_replaceable: bool,
+    /// Stems of words belonging to a synonym group -> key of the group
+    synonym_groups: HashMap<String, String>,
 
     /// current pos in words
     pos: u32,
-    /// Hashmap of repetitions
-    hash: HashMap<String, (u32, Vec<usize>)>,
+    /// Hashmap of repetitions: stem -> (position and sentence of last occurrence, occurrences)
+    hash: HashMap<String, (u32, u32, Vec<usize>)>,
+    /// Keys of `hash`, grouped by length, only used for fuzzy matching
+    fuzzy_index: LengthIndex,
     /// Mapping between non-ignored and ignored words
     pos_to_i: Vec<usize>,
     /// number of words that have been processed before
     offset: usize,
+    /// number of sentences that have been processed before
+    sentence_offset: u32,
+    /// number of characters that have been processed before
+    char_offset: u32,
 }
 
 impl Parser {
@@ -76,6 +505,50 @@ impl Parser {
         Stemmer::list()
     }
 
+    /// Returns what is supported for each language that can be given to `Parser::new`.
+    ///
+    /// Unlike `list_languages`, this also includes "no_stemmer" and the languages that can be
+    /// used without stemming (e.g. catalan), and tells whether there is a builtin list of
+    /// ignored words for each language (see also `Parser::capabilities`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let languages = caribon::Parser::language_info();
+    /// let french = languages.iter().find(|l| l.name == "french").unwrap();
+    /// assert!(french.stemmer_available && french.has_ignore_defaults);
+    /// let german = languages.iter().find(|l| l.name == "german").unwrap();
+    /// assert!(german.stemmer_available && !german.has_ignore_defaults);
+    /// let none = languages.iter().find(|l| l.name == "no_stemmer").unwrap();
+    /// assert!(!none.stemmer_available && !none.has_ignore_defaults);
+    /// let catalan = languages.iter().find(|l| l.name == "catalan").unwrap();
+    /// assert!(!catalan.stemmer_available);
+    /// ```
+    pub fn language_info() -> Vec<LanguageInfo> {
+        let mut res: Vec<LanguageInfo> = Parser::list_languages()
+                                              .into_iter()
+                                              .map(|lang| LanguageInfo {
+                                                  name: lang,
+                                                  has_ignore_defaults:
+                                                      !Parser::get_ignored_from_lang(lang).is_empty(),
+                                                  stemmer_available: true,
+                                              })
+                                              .collect();
+        for &lang in UNSTEMMED_LANGUAGES {
+            res.push(LanguageInfo {
+                name: lang,
+                has_ignore_defaults: !Parser::get_ignored_from_lang(lang).is_empty(),
+                stemmer_available: false,
+            });
+        }
+        res.push(LanguageInfo {
+            name: "no_stemmer",
+            has_ignore_defaults: false,
+            stemmer_available: false,
+        });
+        res
+    }
+
     /// Returns a vector of ignored words from a string.
     ///
     /// # Arguments
@@ -89,6 +562,13 @@ impl Parser {
     /// let v = caribon::Parser::get_ignored_from_string("some, words; to ignore");
     /// assert_eq!(v.len(), 4);
     /// ```
+    ///
+    /// Accented letters are alphabetic, so they don't split words:
+    ///
+    /// ```rust
+    /// let v = caribon::Parser::get_ignored_from_string("bien sûr, déjà");
+    /// assert_eq!(v, vec!["bien", "sûr", "déjà"]);
+    /// ```
     pub fn get_ignored_from_string(list: &str) -> Vec<String> {
         list.split(|c: char| !c.is_alphabetic())
             .filter(|s| !s.is_empty())
@@ -97,14 +577,88 @@ impl Parser {
     }
 
     /// Returns a vector containing the default ignored words for this language.
+    ///
+    /// The language can be given either by its name (as for `Parser::new`) or by its
+    /// ISO 639-1 code.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let v = caribon::Parser::get_ignored_from_lang("swedish");
+    /// assert!(v.contains(&"och".to_owned()));
+    /// assert_eq!(v, caribon::Parser::get_ignored_from_lang("sv"));
+    /// assert!(caribon::Parser::get_ignored_from_lang("no_stemmer").is_empty());
+    /// ```
     pub fn get_ignored_from_lang(lang: &str) -> Vec<String> {
-        if lang.starts_with("fr") {
-            Parser::get_ignored_from_string(IGNORED_FR)
-        } else if lang.starts_with("en") {
-            Parser::get_ignored_from_string(IGNORED_EN)
-            
-        } else {
-            vec![]
+        match lang_code(lang) {
+            Some("fr") => Parser::get_ignored_from_string(IGNORED_FR),
+            Some("en") => Parser::get_ignored_from_string(IGNORED_EN),
+            Some("da") => Parser::get_ignored_from_string(IGNORED_DA),
+            Some("no") => Parser::get_ignored_from_string(IGNORED_NO),
+            Some("sv") => Parser::get_ignored_from_string(IGNORED_SV),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the builtin list of common words for this language, from the most
+    /// frequent to the least frequent (used by `detect_local_weighted`).
+    fn get_common_from_lang(lang: &str) -> Vec<String> {
+        match lang_code(lang) {
+            Some("fr") => Parser::get_ignored_from_string(COMMON_FR),
+            Some("en") => Parser::get_ignored_from_string(COMMON_EN),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the characters that are part of a word for this language, besides letters.
+    ///
+    /// These characters don't split a word when they are between two letters (e.g. the
+    /// middle dot in catalan "col·lecció"). They are used by default when creating a parser
+    /// for this language, even for languages that the stemming library doesn't support
+    /// (see `Parser::new`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// assert_eq!(caribon::Parser::get_word_chars_from_lang("catalan"), vec!['·']);
+    /// assert!(caribon::Parser::get_word_chars_from_lang("english").is_empty());
+    /// ```
+    pub fn get_word_chars_from_lang(lang: &str) -> Vec<char> {
+        match lang_code(lang) {
+            Some("ca") => WORD_CHARS_CA.chars().collect(),
+            Some("br") => WORD_CHARS_BR.chars().collect(),
+            _ => vec![],
+        }
+    }
+
+    /// Returns the features that are supported for a language.
+    ///
+    /// Some languages are only partially supported (e.g. there is a stemmer but no list
+    /// of ignored words), so frontends can use this to disable some features.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let caps = caribon::Parser::capabilities("english");
+    /// assert!(caps.stemmer && caps.stopwords && caps.frequency_list);
+    /// assert!(caps.sentence_terminators && caps.word_chars);
+    ///
+    /// let caps = caribon::Parser::capabilities("german");
+    /// assert!(caps.stemmer);
+    /// assert!(!caps.stopwords && !caps.sentence_terminators);
+    ///
+    /// let caps = caribon::Parser::capabilities("no_stemmer");
+    /// assert!(!caps.stemmer && !caps.stopwords && !caps.frequency_list);
+    /// assert!(!caps.sentence_terminators && !caps.word_chars);
+    /// ```
+    pub fn capabilities(lang: &str) -> Capabilities {
+        let letters_only = lang_code(lang).is_some_and(|code| LETTERS_ONLY.contains(&code));
+        Capabilities {
+            stemmer: Parser::list_languages().contains(&lang),
+            stopwords: !Parser::get_ignored_from_lang(lang).is_empty(),
+            frequency_list: !Parser::get_common_from_lang(lang).is_empty(),
+            sentence_terminators: !Parser::get_abbreviations_from_lang(lang).is_empty(),
+            word_chars: letters_only || !Parser::get_word_chars_from_lang(lang).is_empty(),
         }
     }
 
@@ -114,7 +668,9 @@ impl Parser {
     ///
     /// `lang` – The input text language. This will be used to create the
     ///          stemmer; it also determines what list of ignored words to use.
-    ///          If `lang == "no_stemmer"`, stemming is disabled
+    ///          If `lang == "no_stemmer"`, stemming is disabled. Catalan and breton are
+    ///          not supported by the stemming library, but can still be used for their
+    ///          word characters (see `get_word_chars_from_lang`): words are then not stemmed.
     ///
     /// # Example
     ///
@@ -132,32 +688,89 @@ impl Parser {
     /// let result = caribon::Parser::new("no_stemmer");
     /// assert!(result.is_ok());
     /// ```
+    ///
+    /// ```
+    /// let mut parser = caribon::Parser::new("swedish").unwrap();
+    /// let mut ast = parser.tokenize("Jag såg bilen på vägen och bilar överallt.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "Jag såg **bilen** på vägen och **bilar** överallt.");
+    /// ```
+    ///
+    /// ```
+    /// let mut parser = caribon::Parser::new("catalan").unwrap();
+    /// let ast = parser.tokenize("Una col·lecció de llibres").unwrap();
+    /// let words: Vec<_> = ast.tracked().map(|w| w.as_str()).collect();
+    /// assert_eq!(words, vec!["Una", "col·lecció", "de", "llibres"]);
+    /// ```
     pub fn new(lang: &str) -> Result<Parser> {
         let stemmer;
         if lang == "no_stemmer" {
             stemmer = None;
         } else {
             stemmer = Stemmer::new(lang);
-            if stemmer.is_none() {
+            if stemmer.is_none() && !UNSTEMMED_LANGUAGES.contains(&lang) {
                 return Err(Error {
                     content: format!("Language {} is not implemented.\nSupported languages: {}",
                                      lang,
                                      Parser::list_languages().join(", ")),
+                    position: None,
                 });
             }
         }
         let ignored = Parser::get_ignored_from_lang(lang);
         Ok(Parser {
+            lang: lang.to_owned(),
             stemmer: stemmer,
             ignored: ignored,
+            ignored_phrases: vec![],
+            adaptive_stoplist: None,
+            word_chars: Parser::get_word_chars_from_lang(lang),
             html: true,
-            ignore_proper: false,
+            proper_noun_mode: ProperNounMode::Off,
+            case_sensitive: false,
             max_distance: 50,
+            leak: 0.98,
+            distance_unit: DistanceUnit::Words,
             fuzzy: None,
+            fuzzy_max_distance: None,
+            max_sentence_distance: None,
+            min_occurrences: 0,
+            max_highlights: None,
+            colour_scale: DEFAULT_COLOUR_SCALE.iter().map(|&(m, c)| (m, c.to_owned())).collect(),
+            colour_definitions: HashMap::new(),
+            html_tooltips: false,
+            mark_fuzzy: false,
+            highlight_markers: ("[[".to_owned(), "]]".to_owned()),
+            collapse_highlights: false,
+            paragraph_density: false,
+            lowercase_classes: true,
+            margins: (0, 0),
+            ignore_headings: false,
+            ignore_table_headers: false,
+            single_sentence: false,
+            sentence_terminators: vec!['.', '!', '?'],
+            abbreviations: Parser::get_abbreviations_from_lang(lang),
+            ignore_enumerations: false,
+            subtitles: false,
+            markdown: false,
+            dehyphenate: false,
+            technical_tokens: None,
+            track_numbers: false,
+            html_summary: None,
+            spelling: None,
+            stemming: true,
+            aggressive_stemming: false,
+            synonyms: None,
+            only_replaceable: false,
+            synonym_groups: HashMap::new(),
             pos: 1,
             hash: HashMap::new(),
+            fuzzy_index: BTreeMap::new(),
             pos_to_i: vec!(0),
             offset: 0,
+            sentence_offset: 0,
+            char_offset: 0,
         })
     }
 
@@ -187,6 +800,104 @@ impl Parser {
         self
     }
 
+    /// Sets the maximal edit distance for fuzzy string matching (default none).
+    ///
+    /// With fuzzy string matching, the maximal distance between two words is proportional
+    /// to their length, so long words can be merged even if they are quite different. This
+    /// sets a limit to this distance, regardless of the ratio set by `with_fuzzy`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let text = "After consideration, the confederation agreed";
+    /// let parser = caribon::Parser::new("no_stemmer").unwrap()
+    ///                                                .with_fuzzy(Some(0.3));
+    /// assert_eq!(parser.fuzzy_diff(text, 0.3).unwrap().len(), 1);
+    ///
+    /// let parser = parser.with_fuzzy_max_distance(1);
+    /// assert!(parser.fuzzy_diff(text, 0.3).unwrap().is_empty());
+    /// ```
+    pub fn with_fuzzy_max_distance(mut self, max_distance: u32) -> Parser {
+        self.fuzzy_max_distance = Some(max_distance);
+        self
+    }
+
+    /// Sets spelling normalization for english (default None).
+    ///
+    /// If set to `Some(spelling)`, words that are spelled differently in british and american
+    /// english (e.g. "colour" and "color") are converted to this spelling before being stemmed,
+    /// so they are counted as the same word. This only uses a small list of known variants;
+    /// words whose spelling depends on their meaning (e.g. "licence", "program") are left alone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, Spelling};
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                    .with_spelling_normalization(Some(Spelling::American));
+    /// let mut ast = parser.tokenize("Pick a colour, any color").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "Pick a **colour**, any **color**");
+    ///
+    /// let mut parser = Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("Pick a colour, any color").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "Pick a colour, any color");
+    /// ```
+    pub fn with_spelling_normalization(mut self, spelling: Option<Spelling>) -> Parser {
+        self.spelling = spelling;
+        self
+    }
+
+    /// Sets whether words are stemmed (default true).
+    ///
+    /// By default, words are reduced to their stem, so that e.g. "runs" and "running" are
+    /// counted as repetitions of the same word. If set to false, only the exact (lowercase)
+    /// forms are compared, which can be useful to check the wording of poetry or legal texts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false);
+    /// let mut ast = parser.tokenize("Runs, runs, run").unwrap();
+    /// parser.detect_local(&mut ast, 2.5);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "**Runs**, **runs**, **run**");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false)
+    ///                                                 .with_stemming(false);
+    /// let mut ast = parser.tokenize("Runs, runs, run").unwrap();
+    /// parser.detect_local(&mut ast, 2.5);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "Runs, runs, run");
+    /// ```
+    pub fn with_stemming(mut self, stemming: bool) -> Parser {
+        self.stemming = stemming;
+        self
+    }
+
+    /// Sets whether stems are truncated for agglutinative languages (default false).
+    ///
+    /// In agglutinative languages such as finnish or hungarian, words can have many suffixes
+    /// (and their root can change), so the stemmer often doesn't merge inflected forms of
+    /// the same word. If set to true, for these languages, stems are truncated to their first
+    /// five characters. This merges more forms of a word, at the cost of also merging some
+    /// unrelated words beginning the same way. This has no effect for other languages.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("finnish").unwrap()
+    ///                                             .with_aggressive_stemming(true);
+    /// let mut ast = parser.tokenize("Kaupunki on kaunis. Asun kaupungissa, ja pidän \
+    ///                                kaupungista.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "**Kaupunki** on kaunis. Asun **kaupungissa**, ja pidän **kaupungista**.");
+    /// ```
+    pub fn with_aggressive_stemming(mut self, aggressive: bool) -> Parser {
+        self.aggressive_stemming = aggressive;
+        self
+    }
+
     /// Sets max distance for repetitions (default 50).
     ///
     /// # Arguments
@@ -219,723 +930,5628 @@ impl Parser {
         self
     }
 
+    /// Returns the max distance set by `with_max_distance`
+    pub(crate) fn max_distance(&self) -> u32 {
+        self.max_distance
+    }
+
+    /// Sets the leak factor used by `detect_leak` (default 0.98).
+    ///
+    /// The contribution of an occurrence of a word to the repetition value of another
+    /// occurrence is multiplied by this factor for each word between them (see `detect_leak`),
+    /// so a lower value means that only closer repetitions are detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `leak` – A factor between 0.0 and 1.0
+    pub fn with_leak(mut self, leak: f32) -> Parser {
+        self.leak = leak;
+        self
+    }
+
+    /// Sets the unit in which the max distance for repetitions is measured (default `Words`).
+    ///
+    /// With `DistanceUnit::Characters`, the distance between two occurrences of a word is the
+    /// number of characters between their beginnings, so many short words between them count
+    /// less than a few long ones. This can be useful for poetry or dense text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, DistanceUnit};
+    /// let text = "A cat, a b c d e f g h i j k, a cat. A dog, an extraordinarily long sentence, a dog.";
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_max_distance(8)
+    ///                                        .with_more_ignored("b c d e f g h i j k an");
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "A cat, a b c d e f g h i j k, a cat. A **dog**, an extraordinarily long sentence, a **dog**.");
+    ///
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_max_distance(40)
+    ///                                        .with_distance_unit(DistanceUnit::Characters)
+    ///                                        .with_more_ignored("b c d e f g h i j k an");
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "A **cat**, a b c d e f g h i j k, a **cat**. A dog, an extraordinarily long sentence, a dog.");
+    /// ```
+    pub fn with_distance_unit(mut self, unit: DistanceUnit) -> Parser {
+        self.distance_unit = unit;
+        self
+    }
+
+    /// Sets max distance for repetitions in sentences (default None).
+    ///
+    /// If set, two occurrences of a word are only counted as a repetition by `detect_local`
+    /// if they are separated by less than `max_dist` words *and* at most `max_sentence_dist`
+    /// sentences (so `Some(0)` only counts repetitions inside the same sentence).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_max_sentence_distance(Some(1));
+    /// let mut ast = parser.tokenize("The cat sleeps. It is. So. Yes. No. The cat eats.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "The cat sleeps. It is. So. Yes. No. The cat eats.");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("The cat sleeps. It is. So. Yes. No. The cat eats.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "The **cat** sleeps. It is. So. Yes. No. The **cat** eats.");
+    /// ```
+    pub fn with_max_sentence_distance(mut self, max_sentence_dist: Option<u32>) -> Parser {
+        self.max_sentence_distance = max_sentence_dist;
+        self
+    }
+
+    /// Sets the minimal number of occurrences of a word in the whole text for it to be
+    /// highlighted by `detect_local` (default 0).
+    ///
+    /// A word that is used only twice is rarely a real problem, even if both occurrences are
+    /// close. With fuzzy matching, occurrences of words that have been merged are counted
+    /// together.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_min_occurrences(3);
+    /// let mut ast = parser.tokenize("A cat and a cat, then a dog, a dog and a dog").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "A cat and a cat, then a **dog**, a **dog** and a **dog**");
+    /// ```
+    pub fn with_min_occurrences(mut self, n: u32) -> Parser {
+        self.min_occurrences = n;
+        self
+    }
+
+    /// Only highlights the words with the `n` highest repetition values (default: no limit).
+    ///
+    /// This applies to all `detect_*` methods, and avoids highlighting half of a dense text.
+    /// The limit is on different words (or rather stems), using the highest value of each:
+    /// all the highlighted occurrences of these words are kept. Ties at the cutoff are all
+    /// kept too, so more than `n` words can be highlighted if several have the same value as
+    /// the `n`th one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_max_highlights(2);
+    /// let text = "dog cat cow pig hen fox dog cat cow pig hen dog cat cow pig dog cat cow dog cat dog";
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_global(&mut ast, 0.09);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "**dog** **cat** cow pig hen fox **dog** **cat** cow pig hen **dog** \
+    ///                      **cat** cow pig **dog** **cat** cow **dog** **cat** **dog**");
+    /// ```
+    pub fn with_max_highlights(mut self, n: usize) -> Parser {
+        self.max_highlights = Some(n);
+        self
+    }
+
     /// Sets HTML detection in input (default true).
     ///
     /// You should set it to `false` if a text is text-formatted, and to
     /// `true` if it contains HTML. 
+    ///
+    /// Words inside `<head>` are not tracked. If the document has a head but no `<body>` tag,
+    /// everything after `</head>` is considered as its body. Words inside `<pre>`, `<code>`,
+    /// `<script>` or `<style>` elements are not tracked either, as they are not prose.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+    /// let mut ast = parser.tokenize("<head><title>Title</title></head>\
+    ///                                <p>Some text, more text</p>").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "<head><title>Title</title></head><p>Some **text**, more **text**</p>");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+    /// let mut ast = parser.tokenize("<head><title>Text</title></head>\
+    ///                                <body><p>Some text, more text</p></body>").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "<head><title>Text</title></head><body><p>Some **text**, more **text**</p></body>");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+    /// let mut ast = parser.tokenize("<p>Call <code>print(value, value)</code>.</p>\
+    ///                                <pre><code>value</code> value</pre><br/>\
+    ///                                <p>The value, a value.</p>").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "<p>Call <code>print(value, value)</code>.</p>\
+    ///             <pre><code>value</code> value</pre><br/>\
+    ///             <p>The **value**, a **value**.</p>");
+    /// ```
     pub fn with_html(mut self, html: bool) -> Parser {
         self.html = html;
         self
     }
 
-    /// Sets whether repetition detection should ignore proper nouns (default false).
+    /// Sets whether HTML output displays the repetition value of highlighted words in a tooltip
+    /// (default false).
     ///
-    /// Basically, if set to `true`, words that start with a capital and are not at the beginning of
-    /// a sentence won't be counted for repetitions. Currently, there are still counted if they are in the beginning of
-    /// a sentence, but with most texts it won't be enough to highligth them as repetitions.
-    pub fn with_ignore_proper(mut self, proper: bool) -> Parser {
-        self.ignore_proper = proper;
+    /// For `detect_local`, this value is the number of occurrences of the word in the
+    /// repetition; for `detect_global`, it is the ratio of this word in the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html_tooltips(true);
+    /// let mut ast = parser.tokenize("This word is repeated in a few words, a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert!(html.contains("title = \"repetition value: 3\""));
+    /// ```
+    pub fn with_html_tooltips(mut self, tooltips: bool) -> Parser {
+        self.html_tooltips = tooltips;
         self
     }
 
-    /// Sets the ignored list with a list of words contained in the argument string.
+    /// Sets whether highlighted words that were merged with another word by fuzzy matching
+    /// are displayed differently (default false).
     ///
-    /// This method *replaces* the default list of ignored words. If you want to *add*
-    /// ignored words to the default list of a language, use `with_ignored` instead.
+    /// In HTML output, they get the `caribon-fuzzy` class and a dotted underline; in terminal
+    /// output, they are also in italics. This allows to check whether a fuzzy merge (e.g. of
+    /// "colour" and "color") was intended. Only the words whose stem was replaced are marked,
+    /// not the first occurrence they were merged with.
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `list` – A comma or whitespace separated list of words that should be ignored.
-    pub fn with_ignored(mut self, list: &str) -> Parser {
-        self.ignored = Parser::get_ignored_from_string(list);
+    /// ```rust
+    /// use caribon::Word;
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_fuzzy(Some(0.3))
+    ///                                             .with_mark_fuzzy(true);
+    /// let mut ast = parser.tokenize("A colour, a color").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let fuzzy: Vec<_> = ast.words.iter().filter_map(|w| match *w {
+    ///     Word::Tracked(ref s, _, _, _, fuzzy) => Some((&**s, fuzzy)),
+    ///     _ => None,
+    /// }).collect();
+    /// assert_eq!(fuzzy, vec![("colour", false), ("color", true)]);
+    ///
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert_eq!(html.matches("caribon-fuzzy").count(), 1);
+    /// assert!(html.contains("text-decoration: underline dotted;"));
+    /// ```
+    pub fn with_mark_fuzzy(mut self, mark_fuzzy: bool) -> Parser {
+        self.mark_fuzzy = mark_fuzzy;
         self
     }
 
-    /// Appends a list of words contained in the argument string to the list of ignored words
+    /// Sets the strings written around highlighted words by `ast_to_text` (default "[[" and
+    /// "]]").
     ///
-    /// # Arguments
+    /// # Example
     ///
-    /// * `list` – A comma or whitespace separated list of words that should be ignored.
-    pub fn with_more_ignored(mut self, list: &str) -> Parser {
-        list.split(|c: char| !c.is_alphabetic())
-            .fold((), |_, s| {
-                self.ignored.push(s.to_owned());
-            });
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_highlight_markers("<<", ">>");
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_text(&ast), "This <<word>> is repeated in a few <<words>>");
+    /// ```
+    pub fn with_highlight_markers(mut self, open: &str, close: &str) -> Parser {
+        self.highlight_markers = (open.to_owned(), close.to_owned());
         self
     }
 
-    /// When we know it is the beginning of an escape character (e.g. &nbsp;)
-    fn tokenize_escape<'b>(&self, c: &'b [char]) -> TokenizeResult<'b> {
-        let mut res = String::new();
-        let mut chars: &[char] = c;
+    /// Sets whether HTML output collapses consecutive highlighted words (default false).
+    ///
+    /// If set to true, highlighted words that are only separated by whitespace (e.g. a
+    /// repeated expression) are wrapped in a single span, underlined in the colour of the
+    /// first word, so the underline is continuous instead of being broken between words.
+    /// Each word still has its own span.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_collapse_highlights(true);
+    /// let mut ast = parser.tokenize("Big red dogs. Big red dogs.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert_eq!(html.matches("<span class = \"caribon-run\"").count(), 2);
+    /// assert!(html.starts_with("<span class = \"caribon-run\" style = \"text-decoration: underline; color: green;\"><span class = \"big\""));
+    /// assert!(html.contains("dogs</span></span>."));
+    /// ```
+    pub fn with_collapse_highlights(mut self, collapse: bool) -> Parser {
+        self.collapse_highlights = collapse;
+        self
+    }
 
-        loop {
-            if chars.is_empty() {
-                return Err(Error::new("Error reading HTML: ill-formed escape code. Maybe this \
+    /// Sets whether HTML output annotates paragraphs with their density of repetitions
+    /// (default false).
+    ///
+    /// If set to true, each paragraph gets a `data-repetition-density` attribute, containing
+    /// the proportion of highlighted words among its words, so a stylesheet can e.g. tint
+    /// whole paragraphs. If the input is HTML, the attribute is added to `<p>` elements;
+    /// else, paragraphs are separated by blank lines and wrapped in
+    /// `<div class = "caribon-paragraph">` elements.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html(false)
+    ///                                             .with_paragraph_density(true);
+    /// let mut ast = parser.tokenize("A cat and a cat.\n\nThe sun is bright.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert!(html.starts_with("<div class = \"caribon-paragraph\" data-repetition-density = \"0.40\">"));
+    /// assert!(html.contains("<div class = \"caribon-paragraph\" data-repetition-density = \"0.00\">"));
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_paragraph_density(true);
+    /// let mut ast = parser.tokenize("<p>A cat and a cat.</p><p>The sun is bright.</p>").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert!(html.starts_with("<p data-repetition-density = \"0.40\">"));
+    /// assert!(html.contains("<p data-repetition-density = \"0.00\">The"));
+    /// ```
+    pub fn with_paragraph_density(mut self, paragraph_density: bool) -> Parser {
+        self.paragraph_density = paragraph_density;
+        self
+    }
+
+    /// Sets whether the CSS classes used in HTML output are lowercased (default true).
+    ///
+    /// In HTML output, each tracked word has a CSS class corresponding to its stem, which is
+    /// used to highlight the other occurrences of a word when the mouse is over it. Stems
+    /// are normally lowercase, but they can contain uppercase letters if they are set manually
+    /// (see `Word::set_stemmed`); if this option is set to true, words whose stems only differ
+    /// by case are still highlighted together. In all cases, characters that are not letters,
+    /// digits, '-' or '_' are replaced by '_' in classes, and the displayed text is unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("Word and word").unwrap();
+    /// ast.words[0].set_stemmed("Word".to_owned());
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert_eq!(html.matches("class = \"word\"").count(), 2);
+    /// assert!(html.contains(">Word</span>"));
+    ///
+    /// let parser = parser.with_lowercase_classes(false);
+    /// let html = parser.ast_to_html(&mut ast, false);
+    /// assert_eq!(html.matches("class = \"word\"").count(), 1);
+    /// assert_eq!(html.matches("class = \"Word\"").count(), 1);
+    /// ```
+    pub fn with_lowercase_classes(mut self, lowercase: bool) -> Parser {
+        self.lowercase_classes = lowercase;
+        self
+    }
+
+    /// Sets whether standalone HTML output should contain a summary of the most repeated words
+    /// (default None).
+    ///
+    /// If set to `Some(n)`, a panel listing the `n` most frequent words that have been highlighted
+    /// at least once is added to the page, with links to their first occurrence.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html_summary(Some(1));
+    /// let mut ast = parser.tokenize("This word is repeated in a few words, a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html(&mut ast, true);
+    /// assert!(html.contains("<li><a href = \"#caribon-word\">word</a> (3)</li>"));
+    /// assert!(html.contains("id = \"caribon-word\""));
+    /// assert!(!html.contains("caribon-few"));
+    /// ```
+    pub fn with_html_summary(mut self, summary: Option<usize>) -> Parser {
+        self.html_summary = summary;
+        self
+    }
+
+    /// Sets whether repetition detection should ignore proper nouns (default false).
+    ///
+    /// Basically, if set to `true`, words that start with a capital and are not at the beginning of
+    /// a sentence won't be counted for repetitions. Currently, there are still counted if they are in the beginning of
+    /// a sentence, but with most texts it won't be enough to highligth them as repetitions.
+    ///
+    /// A capitalized word that also appears in lowercase elsewhere in the text (e.g. "Market"
+    /// and "market") is probably a common word being emphasized, so it is still counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignore_proper(true);
+    /// let mut ast = parser.tokenize("I met Paris in the Market, then Paris went to the market").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "I met Paris in the **Market**, then Paris went to the **market**");
+    /// ```
+    pub fn with_ignore_proper(mut self, proper: bool) -> Parser {
+        self.proper_noun_mode = if proper {
+            ProperNounMode::IgnoreCapitalized
+        } else {
+            ProperNounMode::Off
+        };
+        self
+    }
+
+    /// Sets how proper nouns are detected and ignored (default `ProperNounMode::Off`).
+    ///
+    /// `with_ignore_proper(true)` is the same as `ProperNounMode::IgnoreCapitalized`.
+    /// With `ProperNounMode::IgnoreCapitalizedExceptAcronyms`, words written entirely in
+    /// capitals (e.g. "NASA") are not considered as proper nouns, so repeated acronyms
+    /// are still detected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, ProperNounMode};
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                  .with_proper_noun_mode(ProperNounMode::IgnoreCapitalizedExceptAcronyms);
+    /// let mut ast = parser.tokenize("Visit Paris, Paris, NASA, NASA").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "Visit Paris, Paris, **NASA**, **NASA**");
+    ///
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                  .with_proper_noun_mode(ProperNounMode::IgnoreCapitalized);
+    /// let mut ast = parser.tokenize("Visit Paris, Paris, NASA, NASA").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "Visit Paris, Paris, NASA, NASA");
+    /// ```
+    pub fn with_proper_noun_mode(mut self, mode: ProperNounMode) -> Parser {
+        self.proper_noun_mode = mode;
+        self
+    }
+
+    /// Sets whether words that only differ by case are considered different (default false).
+    ///
+    /// If set to true, e.g. "Rose" (a name) and "rose" (a flower) are not repetitions of
+    /// each other. However, at the beginning of a sentence, a word whose only capital is its
+    /// first letter is still considered as lowercase, since this capital is only due to its
+    /// position (words entirely in capitals, such as "NASA", keep their case).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_case_sensitive(true);
+    /// let mut ast = parser.tokenize("Dogs bark. Small dogs bark too. A Dog is here.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "**Dogs** **bark**. Small **dogs** **bark** too. A Dog is here.");
+    /// ```
+    ///
+    /// This also applies to capitalized words that are not ignored as proper nouns because
+    /// they also appear in lowercase:
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_case_sensitive(true)
+    ///                                             .with_ignore_proper(true);
+    /// let mut ast = parser.tokenize("I saw a rose. Then Rose came.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "I saw a rose. Then Rose came.");
+    /// ```
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Parser {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Excludes the first `head` and the last `tail` words of the text from detection (default 0, 0).
+    ///
+    /// This is useful to skip e.g. an epigraph or trailing notes. These words are marked as
+    /// ignored when the text is tokenized; only words that would otherwise be tracked are counted.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_margins(2, 2);
+    /// let mut ast = parser.tokenize("Intro intro text. Some repeated word and word. Outro outro.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "Intro intro text. Some repeated **word** and **word**. Outro outro.");
+    /// ```
+    pub fn with_margins(mut self, head: usize, tail: usize) -> Parser {
+        self.margins = (head, tail);
+        self
+    }
+
+    /// Sets whether words in headings should be ignored when the input is text (default false).
+    ///
+    /// Headings are detected using markdown syntax: either lines starting with `#`, or lines
+    /// underlined by a line containing only `=` or `-`. This has no effect if `html` is set to true.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html(false)
+    ///                                             .with_ignore_headings(true);
+    /// let text = "# Repetition\n\nSome text.\n\nRepetition again\n================\n\nMore text here.";
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "# Repetition\n\nSome **text**.\n\nRepetition again\n================\n\nMore **text** here.");
+    /// ```
+    pub fn with_ignore_headings(mut self, ignore_headings: bool) -> Parser {
+        self.ignore_headings = ignore_headings;
+        self
+    }
+
+    /// Sets whether words in the header rows of tables should be ignored when the input is
+    /// text (default false).
+    ///
+    /// A header row is a line followed by a separator line, which only contains `-`, `=`,
+    /// `:`, `|`, `+` and spaces. This works for markdown pipe tables, grid tables and
+    /// fixed-width tables (where columns are separated by spaces in the separator line, e.g.
+    /// `-----  -----`). Table borders and cell separators are never tracked. This has no
+    /// effect if `html` is set to true.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html(false)
+    ///                                             .with_ignore_table_headers(true);
+    /// let text = "| Colour | Second colour |\n|--------|---------------|\n| rose | tulip |\n\n\
+    ///             A rose is a rose.";
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "| Colour | Second colour |\n|--------|---------------|\n\
+    ///                      | **rose** | tulip |\n\nA **rose** is a **rose**.");
+    /// ```
+    pub fn with_ignore_table_headers(mut self, ignore_table_headers: bool) -> Parser {
+        self.ignore_table_headers = ignore_table_headers;
+        self
+    }
+
+    /// Sets whether the whole text should be considered as a single sentence (default false).
+    ///
+    /// This only matters if proper nouns are ignored: since capitalized words are only
+    /// considered proper nouns when they are not at the beginning of a sentence, texts without
+    /// punctuation (such as poetry or lists of words) give inconsistent results. If this option
+    /// is set, all capitalized words are treated the same way.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, Word};
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                    .with_ignore_proper(true)
+    ///                                    .with_single_sentence(true);
+    /// let ast = parser.tokenize("Roses are red\nViolets are blue").unwrap();
+    /// let ignored = ast.words.iter()
+    ///                        .filter(|w| if let Word::Ignored(_) = **w { true } else { false })
+    ///                        .count();
+    /// assert_eq!(ignored, 2); // both "Roses" and "Violets"
+    /// ```
+    pub fn with_single_sentence(mut self, single_sentence: bool) -> Parser {
+        self.single_sentence = single_sentence;
+        self
+    }
+
+    /// Sets the characters that end a sentence (default ".!?").
+    ///
+    /// Sentences matter for proper nouns detection (a capitalized word at the beginning of a
+    /// sentence is not considered a proper noun), and for methods using a distance in sentences.
+    /// A dot followed by a digit (as in "3.14") never ends a sentence, nor a dot after an
+    /// abbreviation (see `with_abbreviations`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_sentence_terminators(".;");
+    /// let ast = parser.tokenize("One; two! Three").unwrap();
+    /// assert_eq!(ast.sentence_numbers(), vec![0, 0, 1, 1, 1]);
+    /// ```
+    pub fn with_sentence_terminators(mut self, terminators: &str) -> Parser {
+        self.sentence_terminators = terminators.chars().collect();
+        self
+    }
+
+    /// Sets the abbreviations whose final dot doesn't end a sentence (default: a builtin
+    /// list for english and french, e.g. "Dr." or "etc.").
+    ///
+    /// Since the beginnings of sentences are used to detect proper nouns, this avoids e.g.
+    /// considering "Smith" in "Dr. Smith" as a common word that is capitalized only because
+    /// it begins a sentence. The downside is that a sentence that really ends with an
+    /// abbreviation doesn't end there either.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` – A list of words, separated by spaces or commas, without their final dot
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, WordKind};
+    /// let mut parser = Parser::new("english").unwrap().with_ignore_proper(true);
+    /// let ast = parser.tokenize("Ask Dr. Smith. Smith knows").unwrap();
+    /// let kinds: Vec<_> = parser.tokens(&ast).into_iter()
+    ///                           .filter(|t| t.text == "Smith")
+    ///                           .map(|t| t.kind)
+    ///                           .collect();
+    /// assert_eq!(kinds, vec![WordKind::Ignored, WordKind::Tracked]);
+    ///
+    /// let mut parser = Parser::new("english").unwrap().with_ignore_proper(true);
+    /// let mut ast = parser.tokenize("Stop! Stop!").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "**Stop**! **Stop**!");
+    /// ```
+    pub fn with_abbreviations(mut self, list: &str) -> Parser {
+        self.abbreviations = Parser::get_ignored_from_string(&list.to_lowercase());
+        self
+    }
+
+    /// Returns the builtin list of abbreviations for a language (see `with_abbreviations`)
+    fn get_abbreviations_from_lang(lang: &str) -> Vec<String> {
+        match lang_code(lang) {
+            Some("fr") => Parser::get_ignored_from_string(ABBREVIATIONS_FR),
+            Some("en") => Parser::get_ignored_from_string(ABBREVIATIONS_EN),
+            _ => vec![],
+        }
+    }
+
+    /// Sets whether repetitions inside an enumeration are ignored (default false).
+    ///
+    /// If set to true, when at least three words are separated by commas or semicolons
+    /// (e.g. "big, big, big"), a word that is repeated inside this enumeration only counts
+    /// once, as the repetition is probably intentional.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignore_enumerations(true);
+    /// let mut ast = parser.tokenize("It was big, big, big. Really.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "It was big, big, big. Really.");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignore_enumerations(true);
+    /// let mut ast = parser.tokenize("It was big. So big. Too big.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "It was **big**. So **big**. Too **big**.");
+    /// ```
+    pub fn with_ignore_enumerations(mut self, ignore_enumerations: bool) -> Parser {
+        self.ignore_enumerations = ignore_enumerations;
+        self
+    }
+
+    /// Sets whether words split by a hyphen at the end of a line are rejoined (default false).
+    ///
+    /// This is useful for text extracted from PDF files or columns, where words are often
+    /// hyphenated at the end of lines (e.g. "exam-\nple"). If set to true, a hyphen followed
+    /// by a newline and by a lowercase letter (possibly after some indentation) doesn't split
+    /// a word, and the word is stemmed without them. The text itself is not modified.
+    ///
+    /// Compound words that happen to be split at their own hyphen (e.g. "well-\nknown") can't
+    /// be distinguished from these, so they are also joined ("wellknown").
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_dehyphenate(true);
+    /// let mut ast = parser.tokenize("One exam-\nple and another example.").unwrap();
+    /// assert_eq!(&parser.tokens(&ast)[2].text, "exam-\nple");
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "One **exam-\nple** and another **example**.");
+    ///
+    /// let ast = parser.tokenize("well-\nknown").unwrap();
+    /// let joined = parser.tokenize("wellknown").unwrap();
+    /// assert_eq!(parser.tokens(&ast)[0].stem, parser.tokens(&joined)[0].stem);
+    /// ```
+    pub fn with_dehyphenate(mut self, dehyphenate: bool) -> Parser {
+        self.dehyphenate = dehyphenate;
+        self
+    }
+
+    /// Adds characters that don't split a word when they are between two letters.
+    ///
+    /// By default, any non-alphabetic character (except the ones returned by
+    /// `get_word_chars_from_lang`) splits a word, so e.g. "aujourd'hui" is tokenized as
+    /// "aujourd" and "hui". A word character at the beginning or the end of a word (e.g. the
+    /// apostrophe in "dogs'") is still not part of it. Words are stemmed with these characters.
+    ///
+    /// # Arguments
+    ///
+    /// * `extra` – A string containing the characters to add.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, WordKind};
+    /// let mut parser = Parser::new("french").unwrap().with_word_chars("'-");
+    /// let ast = parser.tokenize("Aujourd'hui, c'est le bien-être").unwrap();
+    /// let words: Vec<_> = parser.tokens(&ast).into_iter()
+    ///                           .filter(|t| t.kind != WordKind::Untracked)
+    ///                           .map(|t| t.text)
+    ///                           .collect();
+    /// assert_eq!(words, vec!["Aujourd'hui", "c'est", "le", "bien-être"]);
+    ///
+    /// let mut parser = Parser::new("english").unwrap().with_word_chars("'-");
+    /// let ast = parser.tokenize("The dogs' well-being").unwrap();
+    /// let tokens = parser.tokens(&ast);
+    /// assert_eq!(&tokens[2].text, "dogs");
+    /// assert_eq!(&tokens[3].text, "' ");
+    /// assert_eq!(&tokens[4].text, "well-being");
+    /// ```
+    pub fn with_word_chars(mut self, extra: &str) -> Parser {
+        for c in extra.chars() {
+            if !self.word_chars.contains(&c) {
+                self.word_chars.push(c);
+            }
+        }
+        self
+    }
+
+    /// Sets how version numbers, dates and identifiers are tokenized (default `None`).
+    ///
+    /// By default, only letters are part of words, so e.g. "v1.2.3" is split and "v" is
+    /// tracked as a word. If `kind` is set, sequences of digits separated by dots or dashes
+    /// (e.g. "2024-01-15", "1.2.3"), optionally preceded by a letter (e.g. "v1.2.3", "A4"),
+    /// are kept as a single word of this kind. If it is `WordKind::Tracked`, the whole
+    /// (lowercase) token is used as a stem, so repeated versions or dates can be detected.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, WordKind};
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_technical_tokens(Some(WordKind::Untracked));
+    /// let ast = parser.tokenize("Install v1.2.3 and not v1.2.2.").unwrap();
+    /// let tokens = parser.tokens(&ast);
+    /// assert!(tokens.iter().any(|t| t.text == "v1.2.3" && t.kind == WordKind::Untracked));
+    /// assert!(!tokens.iter().any(|t| t.text == "v"));
+    ///
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_technical_tokens(Some(WordKind::Tracked));
+    /// let mut ast = parser.tokenize("Released on 2024-01-15, patched on 2024-01-15.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "Released **on** **2024-01-15**, patched **on** **2024-01-15**.");
+    /// ```
+    pub fn with_technical_tokens(mut self, kind: Option<WordKind>) -> Parser {
+        self.technical_tokens = kind;
+        self
+    }
+
+    /// Sets whether numbers are tracked for repetitions (default false).
+    ///
+    /// By default, numbers are not words, so they are never highlighted. If set to true,
+    /// numbers are tracked, with the number as it is written used as a stem, so repeated
+    /// dates or measurements can be detected. A number can contain dots or commas between
+    /// digits, so "3.14" and "1,000" are single numbers. Digits that are attached to letters
+    /// (e.g. "v2" or "4th") are not numbers, and are still not tracked.
+    ///
+    /// If `with_technical_tokens` is also set, tokens it recognizes (e.g. "2024-01-15") are
+    /// handled by it instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_track_numbers(true);
+    /// let mut ast = parser.tokenize("Revenue: 3.14 billion. Profit: 3.14 million.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "Revenue: **3.14** billion. Profit: **3.14** million.");
+    ///
+    /// let mut ast = parser.tokenize("From 2020 to 2020 with v2 and v2").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "From **2020** to **2020** with **v**2 and **v**2");
+    /// ```
+    pub fn with_track_numbers(mut self, track_numbers: bool) -> Parser {
+        self.track_numbers = track_numbers;
+        self
+    }
+
+    /// Sets whether the input is a subtitle file, in SRT or WebVTT format (default false).
+    ///
+    /// If set to true, cue numbers and identifiers, timing lines (containing "-->") and the
+    /// "WEBVTT" header are not tracked, so only the dialogue is used for repetition detection.
+    /// The distance between two words is still measured in words, across cues. This option
+    /// has no effect if the input is in HTML (see `with_html`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_html(false)
+    ///                                             .with_subtitles(true);
+    /// let srt = "1\n00:00:01,000 --> 00:00:02,000\nWhere is my key?\n\n\
+    ///            2\n00:00:03,000 --> 00:00:04,000 align:start\nThe key was here.\n";
+    /// let mut ast = parser.tokenize(srt).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert!(result.contains("Where is my **key**?"));
+    /// assert!(result.contains("00:00:04,000 align:start\nThe **key** was here."));
+    /// let tokens = parser.tokens(&ast);
+    /// assert!(tokens.iter().all(|t| t.text != "align" || t.kind == caribon::WordKind::Untracked));
+    /// ```
+    pub fn with_subtitles(mut self, subtitles: bool) -> Parser {
+        self.subtitles = subtitles;
+        self
+    }
+
+    /// Sets whether the input is in Markdown (default false).
+    ///
+    /// If set to true, code blocks (fenced by ``` or ~~~), inline code spans and the URLs
+    /// of links are not tracked, so only the prose is used for repetition detection (the text
+    /// of a link is still tracked). This option has no effect if the input is in HTML (see
+    /// `with_html`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, WordKind};
+    /// let text = "Some prose.\n\n```\nlet x = compute();\nlet y = compute();\n```\n\n\
+    ///             Call `compute` or see [compute](http://example.com/compute).";
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_html(false)
+    ///                                        .with_markdown(true);
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert!(parser.ast_to_repetitions(&ast).is_empty());
+    /// let tracked: Vec<_> = parser.tokens(&ast).into_iter()
+    ///                             .filter(|t| t.kind == WordKind::Tracked)
+    ///                             .map(|t| t.text)
+    ///                             .collect();
+    /// assert_eq!(tracked, vec!["Some", "prose", "Call", "or", "see", "compute"]);
+    ///
+    /// let mut parser = Parser::new("english").unwrap().with_html(false);
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert!(!parser.ast_to_repetitions(&ast).is_empty());
+    /// ```
+    pub fn with_markdown(mut self, markdown: bool) -> Parser {
+        self.markdown = markdown;
+        self
+    }
+
+    /// Sets the ignored list with a list of words contained in the argument string.
+    ///
+    /// This method *replaces* the default list of ignored words. If you want to *add*
+    /// ignored words to the default list of a language, use `with_more_ignored` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` – A comma or whitespace separated list of words that should be ignored.
+    pub fn with_ignored(mut self, list: &str) -> Parser {
+        self.ignored = Parser::get_ignored_from_string(list);
+        self
+    }
+
+    /// Appends a list of words contained in the argument string to the list of ignored words
+    ///
+    /// # Arguments
+    ///
+    /// * `list` – A comma or whitespace separated list of words that should be ignored.
+    pub fn with_more_ignored(mut self, list: &str) -> Parser {
+        list.split(|c: char| !c.is_alphabetic())
+            .fold((), |_, s| {
+                self.ignored.push(s.to_owned());
+            });
+        self
+    }
+
+    /// Empties the list of ignored words, so all words are tracked (including the ones of
+    /// the default list of the language, such as "the" in english).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_no_ignored();
+    /// assert!(parser.ignored_words().is_empty());
+    /// let mut ast = parser.tokenize("I saw the the cat").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "I saw **the** **the** cat");
+    /// ```
+    pub fn with_no_ignored(mut self) -> Parser {
+        self.ignored.clear();
+        self
+    }
+
+    /// Returns the current list of ignored words.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let parser = caribon::Parser::new("english").unwrap().with_ignored("foo, bar");
+    /// assert_eq!(parser.ignored_words(), &["foo", "bar"]);
+    /// ```
+    pub fn ignored_words(&self) -> &[String] {
+        &self.ignored
+    }
+
+    /// Sets a list of phrases whose words are ignored when they appear together.
+    ///
+    /// `with_ignored` only works on single words; this allows to ignore multi-word
+    /// expressions (e.g. "bien sûr" or "New York") as a unit, while their words are still
+    /// tracked when they are used elsewhere. Words of a phrase must only be separated by
+    /// whitespace in the text, and they are compared regardless of case.
+    ///
+    /// # Arguments
+    ///
+    /// * `phrases` – The phrases to ignore, with words separated by whitespace
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored_phrases(&["New York"]);
+    /// let mut ast = parser.tokenize("New York is not new, but we love New York.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "New York is not new, but we love New York.");
+    ///
+    /// let mut ast = parser.tokenize("New York, or just York?").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "New York, or just York?");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored_phrases(&["New York"]);
+    /// let mut ast = parser.tokenize("York, or just York?").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "**York**, or just **York**?");
+    /// ```
+    pub fn with_ignored_phrases(mut self, phrases: &[&str]) -> Parser {
+        self.ignored_phrases = phrases.iter()
+                                      .map(|phrase| {
+                                          phrase.split_whitespace()
+                                                .map(|s| s.to_lowercase())
+                                                .collect::<Vec<_>>()
+                                      })
+                                      .filter(|words| !words.is_empty())
+                                      .collect();
+        self
+    }
+
+    /// Ignores the most frequent words of each text, in addition to the ignored list.
+    ///
+    /// When a text is tokenized, words are first counted, and the `percentile` percent most
+    /// frequent stems (rounded up) are ignored, as they are likely to be function words. This
+    /// is useful for languages that don't have a builtin list of ignored words. Words that
+    /// appear only once are never ignored this way.
+    ///
+    /// # Arguments
+    ///
+    /// * `percentile` – The percentage (between 0 and 100) of distinct stems to ignore
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored("")
+    ///                                             .with_adaptive_stoplist(20.0);
+    /// let mut ast = parser.tokenize("the cat and the dog and the bird and the fish saw the cat")
+    ///                     .unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "the **cat** and the dog and the bird and the fish saw the **cat**");
+    /// ```
+    pub fn with_adaptive_stoplist(mut self, percentile: f32) -> Parser {
+        self.adaptive_stoplist = Some(percentile);
+        self
+    }
+
+    /// Ignores the given fraction of the most frequent stems of each text.
+    ///
+    /// This is the same as `with_adaptive_stoplist`, with a fraction (between 0 and 1) instead
+    /// of a percentage. Words in the ignored list are still ignored: the most frequent stems
+    /// are chosen among the remaining words.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored("saw")
+    ///                                             .with_auto_stopwords(0.1);
+    /// let mut ast = parser.tokenize("the cat saw the dog and the cat saw the bird saw the fish")
+    ///                     .unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "the **cat** saw the dog and the **cat** saw the bird saw the fish");
+    /// ```
+    pub fn with_auto_stopwords(self, fraction: f32) -> Parser {
+        self.with_adaptive_stoplist(fraction * 100.0)
+    }
+
+    /// Appends the words contained in a file to the list of ignored words
+    ///
+    /// Words are separated as in `get_ignored_from_string`; lines starting with `#` are
+    /// comments and are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` – The path of the file containing words that should be ignored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::Write;
+    /// let path = std::env::temp_dir().join("caribon_ignored_example.txt");
+    /// let mut file = std::fs::File::create(&path).unwrap();
+    /// file.write_all(b"# Some comment\nrose, tulip\n").unwrap();
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored_from_file(&path).unwrap();
+    /// let mut ast = parser.tokenize("A rose is a rose, some comment is a comment.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "A rose **is** a rose, some **comment** **is** a **comment**.");
+    ///
+    /// let result = caribon::Parser::new("english").unwrap()
+    ///                                             .with_ignored_from_file(&path.join("nope"));
+    /// assert!(result.is_err());
+    /// ```
+    pub fn with_ignored_from_file(mut self, path: &Path) -> Result<Parser> {
+        let mut content = String::new();
+        let result = File::open(path).and_then(|mut f| f.read_to_string(&mut content));
+        if let Err(e) = result {
+            return Err(Error {
+                content: format!("Could not read ignored words from {}: {}", path.display(), e),
+                position: None,
+            });
+        }
+        for line in content.lines().filter(|line| !line.trim_left().starts_with('#')) {
+            self.ignored.extend(Parser::get_ignored_from_string(line));
+        }
+        Ok(self)
+    }
+
+    /// Sets the colours used to highlight local repetitions (default "green", "orange", "red").
+    ///
+    /// # Arguments
+    ///
+    /// * `low` – Colour for repetitions just above the threshold.
+    /// * `medium` – Colour for repetitions above 1.5 times the threshold.
+    /// * `high` – Colour for repetitions above 2 times the threshold.
+    ///
+    /// Names that are not known by the terminal renderer should be registered
+    /// with `with_colour_definition`.
+    pub fn with_colours(mut self, low: &str, medium: &str, high: &str) -> Parser {
+        self.colour_scale = vec![(1.0, low.to_owned()),
+                                 (1.5, medium.to_owned()),
+                                 (2.0, high.to_owned())];
+        self
+    }
+
+    /// Sets the colours used to highlight local repetitions, and the values above which they
+    /// are used (default 1, 1.5 and 2 times the threshold for "green", "orange" and "red").
+    ///
+    /// Words with a repetition value above `m * threshold` are highlighted with the colour of
+    /// the highest such `m`. A word highlighted with a value lower than all of them gets the
+    /// colour of the lowest one.
+    ///
+    /// Colours can be names or any CSS colour (e.g. "#ff8800"), which are used as is in HTML
+    /// output; colours that are not known by the terminal renderer should be registered with
+    /// `with_colour_definition`.
+    ///
+    /// # Arguments
+    ///
+    /// * `stops` – Multiples of the threshold and their colours, in any order. If it is empty,
+    ///   the current scale is kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                        .with_colour_scale(vec![(1.2, "red"), (1.0, "cyan")]);
+    /// let mut ast = parser.tokenize("one two one two one").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let colours: Vec<_> = parser.ast_to_repetitions(&ast).into_iter().map(|r| r.colour).collect();
+    /// assert_eq!(colours, vec!["red", "cyan", "red", "cyan", "red"]);
+    ///
+    /// let custom = String::from("#ff8800");
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                        .with_colour_scale(vec![(1.0, &custom)]);
+    /// let mut ast = parser.tokenize("one two one").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert!(parser.ast_to_html(&mut ast, false).contains("color: #ff8800;"));
+    ///
+    /// // An empty scale is ignored
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_colour_scale(vec![]);
+    /// let mut ast = parser.tokenize("one two one").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(parser.ast_to_repetitions(&ast)[0].colour, "green");
+    /// ```
+    pub fn with_colour_scale(mut self, stops: Vec<(f32, &str)>) -> Parser {
+        if stops.is_empty() {
+            return self;
+        }
+        let mut stops: Vec<_> = stops.into_iter().map(|(m, c)| (m, c.to_owned())).collect();
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        self.colour_scale = stops;
+        self
+    }
+
+    /// Registers a custom colour name, so it can be rendered by both terminal and HTML outputs.
+    ///
+    /// A definition also overrides the builtin colour with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` – The colour name, as given to `with_colours`.
+    /// * `shell` – The terminal escape code to use for this colour (e.g. "\x1B[4;34m").
+    /// * `html` – The CSS colour to use in HTML output (e.g. "#3eb489").
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_colours("mint", "mint", "mint")
+    ///                                             .with_colour_definition("mint", "\x1B[4;36m", "#3eb489");
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert!(parser.ast_to_terminal(&ast).contains("\x1B[4;36mword\x1B[0m"));
+    /// assert!(parser.ast_to_html(&mut ast, false).contains("color: #3eb489;"));
+    /// ```
+    pub fn with_colour_definition(mut self, name: &str, shell: &str, html: &str) -> Parser {
+        self.colour_definitions.insert(name.to_owned(), (shell.to_owned(), html.to_owned()));
+        self
+    }
+
+    /// Sets a synonym provider (default None).
+    ///
+    /// The provider is a closure that takes a word (in lowercase) and returns a list of
+    /// alternatives to it; it must be `Send` and `Sync`. It is used if `with_only_replaceable`
+    /// is also set, and by `suggest_patch`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_synonyms(|word| match word {
+    ///                                                 "big" => vec!["large".to_owned()],
+    ///                                                 _ => vec![],
+    ///                                             });
+    /// ```
+    pub fn with_synonyms<F>(mut self, provider: F) -> Parser
+        where F: Fn(&str) -> Vec<String> + Send + Sync + 'static
+    {
+        self.synonyms = Some(Box::new(provider));
+        self
+    }
+
+    /// Only highlights repetitions that can be replaced (default false).
+    ///
+    /// If set to true and a synonym provider has been set with `with_synonyms`, words for
+    /// which the provider doesn't return any alternative (e.g. technical terms) are never
+    /// highlighted, so the output only shows the repetitions the writer can do something
+    /// about. Without a synonym provider, this option has no effect.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_synonyms(|word| match word {
+    ///                                                 "big" => vec!["large".to_owned()],
+    ///                                                 _ => vec![],
+    ///                                             })
+    ///                                             .with_only_replaceable(true);
+    /// let mut ast = parser.tokenize("A big parser, a big lexer, a parser").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "A **big** parser, a **big** lexer, a parser");
+    /// ```
+    pub fn with_only_replaceable(mut self, only_replaceable: bool) -> Parser {
+        self.only_replaceable = only_replaceable;
+        self
+    }
+
+    /// Sets groups of synonyms whose repetitions are counted together.
+    ///
+    /// All the words of a group are considered as the same word for repetition detection,
+    /// so the overuse of a concept is detected even if different words are used (e.g.
+    /// "said", "stated" and "remarked"). Words are stemmed when this method is called, so
+    /// e.g. "stated" also matches "states"; it should thus be called after other options
+    /// that change stemming, such as `with_spelling_normalization`.
+    ///
+    /// # Arguments
+    ///
+    /// * `groups` – A list of groups of words
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let group = vec!["said".to_owned(), "stated".to_owned(), "remarked".to_owned()];
+    /// let mut parser = caribon::Parser::new("english").unwrap()
+    ///                                             .with_synonym_groups(vec![group]);
+    /// let mut ast = parser.tokenize("Bob said no, Alice stated yes and Carol remarked maybe").unwrap();
+    /// parser.detect_local(&mut ast, 2.5);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "Bob **said** no, Alice **stated** yes and Carol **remarked** maybe");
+    /// ```
+    pub fn with_synonym_groups(mut self, groups: Vec<Vec<String>>) -> Parser {
+        for group in groups {
+            let stems: Vec<String> = group.iter()
+                .map(|word| {
+                    let normalized = self.normalize_spelling(&word.to_lowercase());
+                    self.stem(&normalized)
+                })
+                .collect();
+            if let Some(key) = stems.first().cloned() {
+                for stemmed in stems {
+                    self.synonym_groups.insert(stemmed, key.clone());
+                }
+            }
+        }
+        self
+    }
+
+    /// Returns the configuration of the parser.
+    ///
+    /// The returned `ConfigSnapshot` can be serialized, and given to `from_config` to
+    /// create a parser that gives the same results. The synonym provider set by
+    /// `with_synonyms` is a closure, so it can't be exported and must be set again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::Parser;
+    /// let parser = Parser::new("english").unwrap()
+    ///                                    .with_fuzzy(Some(0.25))
+    ///                                    .with_max_distance(10)
+    ///                                    .with_more_ignored("word");
+    /// let config = parser.export_config();
+    /// assert_eq!(config.max_distance, 10);
+    /// assert!(config.ignored.contains(&"word".to_owned()));
+    /// ```
+    pub fn export_config(&self) -> ConfigSnapshot {
+        ConfigSnapshot {
+            lang: self.lang.clone(),
+            ignored: self.ignored.clone(),
+            ignored_phrases: self.ignored_phrases.clone(),
+            adaptive_stoplist: self.adaptive_stoplist,
+            word_chars: self.word_chars.clone(),
+            html: self.html,
+            proper_noun_mode: self.proper_noun_mode,
+            case_sensitive: self.case_sensitive,
+            max_distance: self.max_distance,
+            leak: self.leak,
+            distance_unit: self.distance_unit,
+            fuzzy: self.fuzzy,
+            fuzzy_max_distance: self.fuzzy_max_distance,
+            max_sentence_distance: self.max_sentence_distance,
+            min_occurrences: self.min_occurrences,
+            max_highlights: self.max_highlights,
+            colour_scale: self.colour_scale.clone(),
+            colour_definitions: self.colour_definitions.clone(),
+            html_tooltips: self.html_tooltips,
+            mark_fuzzy: self.mark_fuzzy,
+            highlight_markers: self.highlight_markers.clone(),
+            collapse_highlights: self.collapse_highlights,
+            paragraph_density: self.paragraph_density,
+            lowercase_classes: self.lowercase_classes,
+            margins: self.margins,
+            ignore_headings: self.ignore_headings,
+            ignore_table_headers: self.ignore_table_headers,
+            single_sentence: self.single_sentence,
+            sentence_terminators: self.sentence_terminators.clone(),
+            abbreviations: self.abbreviations.clone(),
+            ignore_enumerations: self.ignore_enumerations,
+            subtitles: self.subtitles,
+            markdown: self.markdown,
+            dehyphenate: self.dehyphenate,
+            technical_tokens: self.technical_tokens,
+            track_numbers: self.track_numbers,
+            html_summary: self.html_summary,
+            spelling: self.spelling,
+            stemming: self.stemming,
+            aggressive_stemming: self.aggressive_stemming,
+            only_replaceable: self.only_replaceable,
+            synonym_groups: self.synonym_groups.clone(),
+        }
+    }
+
+    /// Returns `Ok(Parser)` with the configuration returned by `export_config`, `Err(Error)`
+    /// if the language is not implemented or the colour scale is not valid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::Parser;
+    /// let text = "This word is repeated in a few words, a few words";
+    /// let mut parser = Parser::new("english").unwrap()
+    ///                                        .with_fuzzy(Some(0.25))
+    ///                                        .with_colours("cyan", "purple", "red")
+    ///                                        .with_html_tooltips(true);
+    /// let mut restored = Parser::from_config(parser.export_config()).unwrap();
+    /// assert_eq!(parser.fingerprint(text), restored.fingerprint(text));
+    ///
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let mut restored_ast = restored.tokenize(text).unwrap();
+    /// restored.detect_local(&mut restored_ast, 1.9);
+    /// assert_eq!(parser.ast_to_html(&mut ast, true), restored.ast_to_html(&mut restored_ast, true));
+    /// ```
+    pub fn from_config(config: ConfigSnapshot) -> Result<Parser> {
+        let mut parser = try!(Parser::new(&config.lang));
+        if config.colour_scale.is_empty() {
+            return Err(Error::new("Colour scale must contain at least one colour"));
+        }
+        parser.colour_scale = config.colour_scale;
+        parser.ignored = config.ignored;
+        parser.ignored_phrases = config.ignored_phrases;
+        parser.adaptive_stoplist = config.adaptive_stoplist;
+        parser.word_chars = config.word_chars;
+        parser.html = config.html;
+        parser.proper_noun_mode = config.proper_noun_mode;
+        parser.case_sensitive = config.case_sensitive;
+        parser.max_distance = config.max_distance;
+        parser.leak = config.leak;
+        parser.distance_unit = config.distance_unit;
+        parser.fuzzy = config.fuzzy;
+        parser.fuzzy_max_distance = config.fuzzy_max_distance;
+        parser.max_sentence_distance = config.max_sentence_distance;
+        parser.min_occurrences = config.min_occurrences;
+        parser.max_highlights = config.max_highlights;
+        parser.colour_definitions = config.colour_definitions;
+        parser.html_tooltips = config.html_tooltips;
+        parser.mark_fuzzy = config.mark_fuzzy;
+        parser.highlight_markers = config.highlight_markers;
+        parser.collapse_highlights = config.collapse_highlights;
+        parser.paragraph_density = config.paragraph_density;
+        parser.lowercase_classes = config.lowercase_classes;
+        parser.margins = config.margins;
+        parser.ignore_headings = config.ignore_headings;
+        parser.ignore_table_headers = config.ignore_table_headers;
+        parser.single_sentence = config.single_sentence;
+        parser.sentence_terminators = config.sentence_terminators;
+        parser.abbreviations = config.abbreviations;
+        parser.ignore_enumerations = config.ignore_enumerations;
+        parser.subtitles = config.subtitles;
+        parser.markdown = config.markdown;
+        parser.dehyphenate = config.dehyphenate;
+        parser.technical_tokens = config.technical_tokens;
+        parser.track_numbers = config.track_numbers;
+        parser.html_summary = config.html_summary;
+        parser.spelling = config.spelling;
+        parser.stemming = config.stemming;
+        parser.aggressive_stemming = config.aggressive_stemming;
+        parser.only_replaceable = config.only_replaceable;
+        parser.synonym_groups = config.synonym_groups;
+        Ok(parser)
+    }
+
+    /// Returns a fingerprint of the parser configuration and of an input text.
+    ///
+    /// Two parsers with the same configuration give the same fingerprint for the same input,
+    /// while changing either the input or any option changes it. This can be used to avoid
+    /// running the detection again if nothing changed.
+    ///
+    /// The synonym provider set by `with_synonyms` is a closure, so only whether there is one
+    /// is taken into account: changing it for another one doesn't change the fingerprint.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let parser = caribon::Parser::new("english").unwrap();
+    /// let fingerprint = parser.fingerprint("Some text");
+    /// assert_eq!(fingerprint, caribon::Parser::new("english").unwrap().fingerprint("Some text"));
+    /// assert!(fingerprint != parser.fingerprint("Some other text"));
+    ///
+    /// let parser = parser.with_fuzzy(Some(0.25));
+    /// assert!(fingerprint != parser.fingerprint("Some text"));
+    /// ```
+    pub fn fingerprint(&self, input: &str) -> u64 {
+        let mut hasher = FnvHasher::new();
+
+        // All options that can change the result must be hashed here
+        self.lang.hash(&mut hasher);
+        self.ignored.hash(&mut hasher);
+        self.ignored_phrases.hash(&mut hasher);
+        self.adaptive_stoplist.map(|x| x.to_bits()).hash(&mut hasher);
+        self.word_chars.hash(&mut hasher);
+        self.html.hash(&mut hasher);
+        self.proper_noun_mode.hash(&mut hasher);
+        self.case_sensitive.hash(&mut hasher);
+        self.max_distance.hash(&mut hasher);
+        self.leak.to_bits().hash(&mut hasher);
+        self.distance_unit.hash(&mut hasher);
+        self.fuzzy.map(|x| x.to_bits()).hash(&mut hasher);
+        self.fuzzy_max_distance.hash(&mut hasher);
+        self.max_sentence_distance.hash(&mut hasher);
+        self.min_occurrences.hash(&mut hasher);
+        self.max_highlights.hash(&mut hasher);
+        for &(m, ref c) in &self.colour_scale {
+            m.to_bits().hash(&mut hasher);
+            c.hash(&mut hasher);
+        }
+        let mut colours: Vec<_> = self.colour_definitions.iter().collect();
+        colours.sort();
+        colours.hash(&mut hasher);
+        self.html_tooltips.hash(&mut hasher);
+        self.mark_fuzzy.hash(&mut hasher);
+        self.highlight_markers.hash(&mut hasher);
+        self.collapse_highlights.hash(&mut hasher);
+        self.paragraph_density.hash(&mut hasher);
+        self.lowercase_classes.hash(&mut hasher);
+        self.margins.hash(&mut hasher);
+        self.ignore_headings.hash(&mut hasher);
+        self.ignore_table_headers.hash(&mut hasher);
+        self.single_sentence.hash(&mut hasher);
+        self.sentence_terminators.hash(&mut hasher);
+        self.abbreviations.hash(&mut hasher);
+        self.ignore_enumerations.hash(&mut hasher);
+        self.subtitles.hash(&mut hasher);
+        self.markdown.hash(&mut hasher);
+        self.dehyphenate.hash(&mut hasher);
+        self.technical_tokens.hash(&mut hasher);
+        self.track_numbers.hash(&mut hasher);
+        self.html_summary.hash(&mut hasher);
+        self.spelling.hash(&mut hasher);
+        self.stemming.hash(&mut hasher);
+        self.aggressive_stemming.hash(&mut hasher);
+        // A closure can't be hashed, so this only tells whether there is a synonym provider
+        self.synonyms.is_some().hash(&mut hasher);
+        self.only_replaceable.hash(&mut hasher);
+        let mut groups: Vec<_> = self.synonym_groups.iter().collect();
+        groups.sort();
+        groups.hash(&mut hasher);
+
+        input.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// When we know it is the beginning of an escape character (e.g. &nbsp;)
+    fn tokenize_escape<'b>(&self, c: &'b [char]) -> TokenizeResult<'b> {
+        let mut chars: &[char] = c;
+
+        loop {
+            if chars.is_empty() {
+                return Err(Error::new("Error reading HTML: ill-formed escape code. Maybe this \
                                        is not an HTML file?"));
             }
-            let c = chars[0];
-            res.push(c);
-            chars = &chars[1..];
-            if c == ';' {
-                return Ok((chars, Word::Untracked(res)));
+            let c = chars[0];
+            chars = &chars[1..];
+            if c == ';' {
+                return Ok((chars, Token::Untracked));
+            }
+        }
+    }
+
+    fn tokenize_html<'b>(&self,
+                         c: &'b [char],
+                         ast: &mut Ast,
+                         in_body: &mut bool,
+                         verbatim: &mut u32)
+                         -> TokenizeResult<'b> {
+        let mut chars: &[char] = c;
+        let mut brackets = 1;
+        let mut was_tag_found = false;
+        // Whether this tag opens (1) or closes (-1) an element whose content is not text
+        let mut verbatim_change = 0;
+
+        chars = &chars[1..];
+
+        // Inner function to determine if a char is part of a possible tag name
+        fn is_tag(c: char) -> bool {
+            c == '[' || c == ']' || c == '/' || c.is_alphabetic()
+        }
+
+        loop {
+            if chars.is_empty() {
+                return Err(Error::new("Error reading HTML: unclosed tag. Maybe this is not an \
+                                       HTML file?"));
+            }
+            let c = chars[0];
+            if !was_tag_found && is_tag(c) {
+                was_tag_found = true;
+
+                let tag: String = chars.iter()
+                                       .take_while(|c: &&char| is_tag(**c))
+                                       .map(|c| c.to_lowercase().collect::<String>())
+                                       .fold(String::new(), |acc, x| acc + &x);
+                match &*tag {
+                    "head" => {
+                        ast.mark_begin_head();
+                        *in_body = false;
+                    }
+                    "/head" => {
+                        // Content after the head is the body, even without a <body> tag
+                        *in_body = true;
+                    }
+                    "body" => {
+                        ast.mark_begin_body();
+                        *in_body = true;
+                    }
+                    "/body" => {
+                        ast.mark_end_body();
+                        *in_body = false;
+                    }
+                    "html" => {
+                        *in_body = false;
+                    }
+                    "pre" | "code" | "script" | "style" => verbatim_change = 1,
+                    "/pre" | "/code" | "/script" | "/style" => verbatim_change = -1,
+                    "[cdata[" => {
+                        // Special loop for CDATA
+                        chars = &chars[1..];
+                        loop {
+                            if chars.len() < 2 {
+                                return Err(Error::new("CDATA field not terminated properly"));
+                            }
+                            if chars[0] == ']' && chars[1] == ']' {
+                                chars = &chars[2..];
+                                break;
+                            } else {
+                                chars = &chars[1..];
+                            }
+                        }
+                        continue;
+                    }
+                    _ => (),
+                }
+            }
+            chars = &chars[1..];
+            if c == '<' {
+                brackets += 1;
+            }
+            if c == '>' {
+                brackets -= 1;
+                if brackets == 0 {
+                    break;
+                }
+            }
+        }
+        if verbatim_change == 1 && !c[..c.len() - chars.len()].ends_with(&['/', '>']) {
+            *verbatim += 1;
+        } else if verbatim_change == -1 {
+            // A closing tag without an opening one is just ignored
+            *verbatim = verbatim.saturating_sub(1);
+        }
+        Ok((chars, Token::Untracked))
+    }
+
+    fn tokenize_whitespace<'b>(&self, c: &'b [char], is_begin: &mut bool) -> TokenizeResult<'b> {
+        let mut chars: &[char] = c;
+        // Whether the last character is not alphanumeric
+        let mut after_separator = false;
+
+        loop {
+            if chars.is_empty() {
+                break;
+            }
+            let c = chars[0];
+            if ((c == '<' || c == '&') && self.html) || c.is_alphabetic() {
+                break;
+            }
+            if self.technical_tokens.is_some() && after_separator &&
+               technical_token_len(chars).is_some() {
+                break;
+            }
+            if self.track_numbers && after_separator && number_len(chars).is_some() {
+                break;
+            }
+            chars = &chars[1..];
+            after_separator = !c.is_alphanumeric();
+            // A dot followed by a digit is a decimal separator
+            let decimal = c == '.' && chars.first().is_some_and(|c| c.is_ascii_digit());
+            if self.sentence_terminators.contains(&c) && !decimal && !self.single_sentence {
+                *is_begin = true;
+            }
+        }
+
+        Ok((chars, Token::Untracked))
+    }
+
+    /// Returns the token for a version number, date or identifier (see `with_technical_tokens`)
+    fn technical_token(&self, s: &[char], in_body: bool) -> Token {
+        match self.technical_tokens {
+            Some(WordKind::Tracked) if in_body => {
+                Token::Tracked(s.iter().flat_map(|c| c.to_lowercase()).collect())
+            }
+            Some(WordKind::Ignored) if in_body => Token::Ignored,
+            _ => Token::Untracked,
+        }
+    }
+
+    /// Return true if `s` is a proper noun, false else
+    fn is_proper_noun(&self, s: &[char], is_begin: bool) -> bool {
+        if self.proper_noun_mode != ProperNounMode::Off {
+            if !is_begin {
+                let o = s.first();
+                match o {
+                    None => false,
+                    Some(c) if !c.is_uppercase() => false,
+                    Some(_) => {
+                        let is_acronym = s.iter().filter(|c| c.is_alphabetic()).count() > 1 &&
+                                         !s.iter().any(|c| c.is_lowercase());
+                        !(is_acronym &&
+                          self.proper_noun_mode == ProperNounMode::IgnoreCapitalizedExceptAcronyms)
+                    }
+                }
+            } else {
+                // Technically a proper noun could be at the beginning of a sentence :s
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    /// Returns the key used to count repetitions of a word, from the characters of the word
+    /// that are used for stemming (i.e. without the line breaks joined by `with_dehyphenate`)
+    fn word_key(&mut self, key: &str, is_begin: bool) -> String {
+        let s: String = key.chars().filter(|c| !is_invisible(*c)).collect();
+        if self.case_sensitive {
+            // A capital that is only due to the beginning of the sentence doesn't count
+            let s = if is_begin { lowercase_initial(&s) } else { s };
+            self.tracking_key(&s)
+        } else {
+            let lower_s: String = s.chars().flat_map(char::to_lowercase).collect();
+            self.tracking_key(&lower_s)
+        }
+    }
+
+    /// Returns the characters of a word that are used for stemming, i.e. without the line
+    /// breaks joined by `with_dehyphenate`
+    fn key_chars(&self, s: &str) -> String {
+        let chars: Vec<char> = s.chars().collect();
+        let mut res = String::with_capacity(s.len());
+        let mut i = 0;
+        while i < chars.len() {
+            if chars[i] == '-' && self.dehyphenate {
+                if let Some(len) = line_break_hyphen_len(&chars[i..]) {
+                    i += len;
+                    continue;
+                }
+            }
+            res.push(chars[i]);
+            i += 1;
+        }
+        res
+    }
+
+    fn tokenize_word<'b>(&mut self,
+                         c: &'b [char],
+                         is_begin: &mut bool,
+                         in_body: bool)
+                         -> TokenizeResult<'b> {
+        // Characters of the word that are used for stemming
+        let mut key = String::new();
+        let mut chars: &[char] = c;
+
+        loop {
+            if chars.is_empty() {
+                break;
+            }
+            let c = chars[0];
+            if c == '-' && self.dehyphenate {
+                if let Some(len) = line_break_hyphen_len(chars) {
+                    chars = &chars[len..];
+                    continue;
+                }
+            }
+            if !c.is_alphabetic() {
+                // Invisible characters inside a word (e.g. soft hyphens in text copied from
+                // a PDF) and word characters of the language don't split it
+                let inside = chars.len() > 1 && chars[1].is_alphabetic();
+                if !(inside && (is_invisible(c) || self.word_chars.contains(&c))) {
+                    break;
+                }
+            }
+            key.push(c);
+            chars = &chars[1..];
+        }
+        let res = &c[..c.len() - chars.len()];
+
+        let lower_s: String = key.chars()
+                                 .filter(|c| !is_invisible(*c))
+                                 .flat_map(char::to_lowercase)
+                                 .collect();
+        let word = if !in_body {
+            // We are not in body, so words are all untracked
+            Token::Untracked
+        } else if self.ignored.contains(&lower_s) || self.is_proper_noun(res, *is_begin) {
+            Token::Ignored
+        } else {
+            Token::Tracked(self.word_key(&key, *is_begin))
+        };
+
+        *is_begin = false;
+        Ok((chars, word))
+    }
+
+
+    /// Tokenize a string into a list of words. 
+    ///
+    /// This is the step that converts a string to some inner representation.
+    ///
+    /// Soft hyphens and zero-width characters inside a word are kept in its text, but
+    /// don't split it and are not taken into account for repetition detection.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` – The string to tokenize.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("An exam\u{00AD}ple").unwrap();
+    /// let tokens = parser.tokens(&ast);
+    /// assert_eq!(tokens.len(), 3);
+    /// assert_eq!(&tokens[2].text, "exam\u{00AD}ple");
+    ///
+    /// let ast = parser.tokenize("An example").unwrap();
+    /// assert_eq!(tokens[2].stem, parser.tokens(&ast)[2].stem);
+    /// ```
+    pub fn tokenize(&mut self, s: &str) -> Result<Ast<'static>> {
+        let v_chars: Vec<char> = s.chars().collect();
+        self.tokenize_chars(&v_chars)
+    }
+
+    /// Tokenize a string into a list of words borrowing their text from it.
+    ///
+    /// This gives the same Ast as `tokenize`, but the words don't own a copy of their text
+    /// and the string is tokenized in chunks, so this uses less memory for long texts. Use
+    /// `Ast::into_owned` to get an Ast that doesn't borrow the string anymore.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` – The string to tokenize.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::borrow::Cow;
+    /// let text = "Some words, and more words.".to_owned();
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize_borrowed(&text).unwrap();
+    /// assert_eq!(ast, parser.tokenize(&text).unwrap());
+    /// match ast.words[2] {
+    ///     caribon::Word::Tracked(Cow::Borrowed(s), _, _, _, _) => assert_eq!(s, "words"),
+    ///     ref word => panic!("unexpected word {:?}", word),
+    /// }
+    /// ```
+    pub fn tokenize_borrowed<'a>(&mut self, s: &'a str) -> Result<Ast<'a>> {
+        if self.needs_whole_text() {
+            let v_chars: Vec<char> = s.chars().collect();
+            let mut state = self.tokenizer_state(&v_chars, Some(s));
+            try!(self.tokenize_chunk(&mut state, &v_chars, 0, 0, true));
+            return Ok(self.tokenize_end(state));
+        }
+        let mut state = self.tokenizer_state(&[], Some(s));
+        let mut buffer = CharBuffer {
+            chars: vec![],
+            start: 0,
+            offset: 0,
+        };
+        let mut chars = s.chars();
+        loop {
+            let len = buffer.chars.len();
+            buffer.chars.extend(chars.by_ref().take(8192));
+            if buffer.chars.len() == len {
+                break;
+            }
+            try!(self.tokenize_buffer(&mut state, &mut buffer));
+        }
+        try!(self.tokenize_chunk(&mut state, &buffer.chars, buffer.start, buffer.offset, true));
+        Ok(self.tokenize_end(state))
+    }
+
+    /// Tokenize several texts (e.g. the chapters of a novel) into a single Ast.
+    ///
+    /// Each text is preceded by an (empty) untracked marker, whose position is stored in the
+    /// `files` field of the Ast with the name of the text, so `Ast::file_at` can tell which
+    /// text a word comes from. Detection methods then work on all texts, e.g. `detect_global`
+    /// finds words that are overused across chapters. Each text begins a new sentence.
+    ///
+    /// If the input is in HTML, only the body of each document is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` – The names and contents of the texts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false);
+    /// let sources = vec![("one.txt".to_owned(), "A lighthouse stood there".to_owned()),
+    ///                    ("two.txt".to_owned(), "Nobody saw the lighthouse".to_owned())];
+    /// let mut ast = parser.tokenize_many(&sources).unwrap();
+    /// parser.detect_global(&mut ast, 0.2);
+    /// let repetitions = parser.repetitions(&ast);
+    /// assert_eq!(repetitions.len(), 1);
+    /// let words: Vec<_> = repetitions[0].positions.iter().map(|&i| ast.words[i].as_str()).collect();
+    /// assert_eq!(words, vec!["lighthouse", "lighthouse"]);
+    /// assert!(repetitions[0].highlighted);
+    /// let files: Vec<_> = repetitions[0].positions.iter().map(|&i| ast.file_at(i)).collect();
+    /// assert_eq!(files, vec![Some("one.txt"), Some("two.txt")]);
+    /// ```
+    pub fn tokenize_many(&mut self, sources: &[(String, String)]) -> Result<Ast<'static>> {
+        let mut res = Ast::new();
+        for (name, content) in sources {
+            let mut ast = try!(self.tokenize(content).map_err(|e| {
+                Error {
+                    content: format!("{}: {}", name, e.content),
+                    position: e.position,
+                }
+            }));
+            if !res.words.is_empty() {
+                res.mark_sentence();
+            }
+            res.mark_file(name);
+            res.words.push(Word::Untracked("".into()));
+
+            let range = ast.body_range();
+            let offset = res.words.len();
+            res.sentences.extend(ast.sentences
+                                    .iter()
+                                    .filter(|&&i| i >= range.start && i < range.end)
+                                    .map(|&i| i - range.start + offset));
+            res.words.extend(ast.words.drain(range));
+        }
+        Ok(res)
+    }
+
+    /// Tokenize the content of a reader into a list of words.
+    ///
+    /// The content is read and decoded from UTF-8 in chunks (handling characters split between
+    /// two chunks), and each chunk is tokenized as soon as it is read, so the whole text is
+    /// never held in memory besides the Ast. A word, tag or escape code split between two
+    /// chunks is kept until the next chunk is read. The Ast is the same as the one returned by
+    /// `tokenize` for the same text.
+    ///
+    /// In text mode, `with_ignore_headings`, `with_ignore_table_headers`, `with_subtitles` and
+    /// `with_markdown` need the whole text, so if one of them is set, the content is read
+    /// before being tokenized.
+    ///
+    /// # Arguments
+    ///
+    /// * `r` – The reader, whose content must be valid UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::io::Write;
+    /// let text = "Élisabeth écrit des lettres à sa sœur. ".repeat(500);
+    /// let path = std::env::temp_dir().join("caribon_tokenize_reader_example.txt");
+    /// std::fs::File::create(&path).unwrap().write_all(text.as_bytes()).unwrap();
+    ///
+    /// let mut parser = caribon::Parser::new("french").unwrap();
+    /// let ast = parser.tokenize_reader(std::fs::File::open(&path).unwrap()).unwrap();
+    /// assert_eq!(ast, parser.tokenize(&text).unwrap());
+    ///
+    /// assert!(parser.tokenize_reader(&[0x66, 0x6f, 0xe9][..]).is_err());
+    /// ```
+    pub fn tokenize_reader<R: Read>(&mut self, mut r: R) -> Result<Ast<'static>> {
+        let whole_text = self.needs_whole_text();
+        let mut state = self.tokenizer_state(&[], None);
+        let mut v_chars = CharBuffer {
+            chars: vec![],
+            start: 0,
+            offset: 0,
+        };
+        let mut buffer = [0; 8192];
+        // Bytes of a character that was split between two chunks
+        let mut pending: Vec<u8> = vec![];
+        loop {
+            let n = match r.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e) if e.kind() == ::std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(Error::new(&format!("Error reading input: {}", e))),
+            };
+            pending.extend_from_slice(&buffer[..n]);
+            let valid = match ::std::str::from_utf8(&pending) {
+                Ok(s) => s.len(),
+                // The end of the chunk can be the beginning of a character
+                Err(ref e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(Error::new(&format!("Input is not valid UTF-8: {}", e))),
+            };
+            v_chars.chars.extend(::std::str::from_utf8(&pending[..valid]).unwrap().chars());
+            pending.drain(..valid);
+            if !whole_text {
+                try!(self.tokenize_buffer(&mut state, &mut v_chars));
+            }
+        }
+        if !pending.is_empty() {
+            return Err(Error::new("Input is not valid UTF-8: incomplete character at the end"));
+        }
+        if whole_text {
+            return self.tokenize_chars(&v_chars.chars);
+        }
+        try!(self.tokenize_chunk(&mut state, &v_chars.chars, v_chars.start, v_chars.offset, true));
+        Ok(self.tokenize_end(state))
+    }
+
+    /// Tokenize a list of chars (see `tokenize`)
+    fn tokenize_chars(&mut self, v_chars: &[char]) -> Result<Ast<'static>> {
+        let mut state = self.tokenizer_state(v_chars, None);
+        try!(self.tokenize_chunk(&mut state, v_chars, 0, 0, true));
+        Ok(self.tokenize_end(state))
+    }
+
+    /// Returns true if the whole text is needed before tokenizing it, i.e. in text mode with
+    /// `with_ignore_headings`, `with_ignore_table_headers`, `with_subtitles` or `with_markdown`
+    fn needs_whole_text(&self) -> bool {
+        !self.html &&
+        (self.ignore_headings || self.ignore_table_headers || self.subtitles || self.markdown)
+    }
+
+    /// Tokenize the characters of the buffer that can be, knowing that more characters follow
+    /// them, and removes them from the buffer (except the ones that are still needed)
+    fn tokenize_buffer(&mut self,
+                       state: &mut TokenizerState,
+                       buffer: &mut CharBuffer)
+                       -> Result<()> {
+        let end = try!(self.tokenize_chunk(state,
+                                           &buffer.chars,
+                                           buffer.start,
+                                           buffer.offset,
+                                           false));
+        let drained = end.saturating_sub(ERROR_CONTEXT);
+        buffer.chars.drain(..drained);
+        buffer.start = end - drained;
+        buffer.offset += drained;
+        Ok(())
+    }
+
+    /// Returns the initial state of the tokenizer for a text
+    ///
+    /// The whole text is only needed to find headings, subtitle headers and Markdown code in
+    /// text mode; else it can be empty. If `source` is given, the words borrow their text
+    /// from it.
+    fn tokenizer_state<'a>(&self, v_chars: &[char], source: Option<&'a str>) -> TokenizerState<'a> {
+        let mut headings = if !self.html && self.ignore_headings {
+            heading_ranges(v_chars)
+        } else {
+            vec![]
+        };
+        if !self.html && self.ignore_table_headers {
+            headings.extend(table_header_ranges(v_chars));
+        }
+        let mut untracked = if !self.html && self.subtitles {
+            subtitle_ranges(v_chars)
+        } else {
+            vec![]
+        };
+        if !self.html && self.markdown {
+            untracked.extend(markdown_code_ranges(v_chars));
+        }
+        // Ranges are sorted so they can be walked through along with the text
+        headings.sort();
+        untracked.sort();
+        TokenizerState {
+            ast: Ast::new(),
+            source,
+            byte: 0,
+            is_sentence_beginning: !self.single_sentence,
+            in_body: true,
+            verbatim: 0,
+            headings,
+            untracked,
+            heading_cursor: 0,
+            untracked_cursor: 0,
+            proper_nouns: vec![],
+            lowercase_forms: HashSet::new(),
+        }
+    }
+
+    /// Tokenize the characters of `v_chars` after `start` (the previous ones are only used to
+    /// look at the characters before a word).
+    ///
+    /// `offset` is the position of `v_chars[0]` in the whole text. If `end` is false, more
+    /// characters follow `v_chars`, so a word (or tag, escape code...) that might continue
+    /// after it is not tokenized yet.
+    ///
+    /// Returns the index of the first character that was not tokenized.
+    fn tokenize_chunk<'a>(&mut self,
+                      state: &mut TokenizerState<'a>,
+                      v_chars: &[char],
+                      start: usize,
+                      offset: usize,
+                      end: bool)
+                      -> Result<usize> {
+        let mut chars: &[char] = &v_chars[start..];
+        let ast = &mut state.ast;
+        let mut is_sentence_beginning = state.is_sentence_beginning;
+        let mut in_body = state.in_body;
+        let mut verbatim = state.verbatim;
+
+        while !chars.is_empty() {
+            let c = chars[0];
+            let pos = v_chars.len() - chars.len();
+            let was_sentence_beginning = is_sentence_beginning;
+            let marks = (in_body, verbatim, ast.begin_head, ast.begin_body, ast.end_body);
+            let technical = match self.technical_tokens {
+                Some(_) if pos == 0 || !v_chars[pos - 1].is_alphanumeric() => {
+                    technical_token_len(chars)
+                }
+                _ => None,
+            };
+            let number = if self.track_numbers && technical.is_none() &&
+                            (pos == 0 || !v_chars[pos - 1].is_alphanumeric()) {
+                number_len(chars)
+            } else {
+                None
+            };
+            let result = if let Some(len) = technical {
+                is_sentence_beginning = false;
+                Ok((&chars[len..], self.technical_token(&chars[..len], in_body)))
+            } else if let Some(len) = number {
+                is_sentence_beginning = false;
+                let token = if in_body {
+                    Token::Tracked(chars[..len].iter().cloned().collect())
+                } else {
+                    Token::Untracked
+                };
+                Ok((&chars[len..], token))
+            } else if c.is_alphabetic() {
+                self.tokenize_word(chars, &mut is_sentence_beginning, in_body)
+            } else if self.html && c == '<' {
+                is_sentence_beginning = false;
+                self.tokenize_html(chars, ast, &mut in_body, &mut verbatim)
+                    .map_err(|e| located_error(e, v_chars, pos, offset))
+            } else if self.html && c == '&' {
+                self.tokenize_escape(chars).map_err(|e| located_error(e, v_chars, pos, offset))
+            } else {
+                let result = self.tokenize_whitespace(chars, &mut is_sentence_beginning);
+                // The dot of an abbreviation doesn't end the sentence
+                if is_sentence_beginning && !was_sentence_beginning && c == '.' {
+                    let previous = ast.words.last().map(|w| w.as_str().to_lowercase());
+                    if previous.is_some_and(|p| self.abbreviations.contains(&p)) {
+                        is_sentence_beginning = false;
+                    }
+                }
+                result
+            };
+            // Unless this is the end of the text, the token might depend on the following
+            // characters (e.g. an unclosed tag, or a word followed by a middle dot), so it is
+            // tokenized again with the next chunk
+            if !end && result.as_ref().map_or(true, |&(cs, _)| cs.len() < TOKEN_LOOKAHEAD) {
+                is_sentence_beginning = was_sentence_beginning;
+                in_body = marks.0;
+                verbatim = marks.1;
+                ast.begin_head = marks.2;
+                ast.begin_body = marks.3;
+                ast.end_body = marks.4;
+                break;
+            }
+            let (cs, token) = try!(result);
+            // Position of the token in the whole text
+            let input_pos = offset + pos;
+            let in_heading = in_ranges(&state.headings, &mut state.heading_cursor, input_pos);
+            let in_untracked = in_ranges(&state.untracked, &mut state.untracked_cursor, input_pos) ||
+                               verbatim > 0;
+            let token_chars = &chars[..chars.len() - cs.len()];
+            let len: usize = token_chars.iter().map(|c| c.len_utf8()).sum();
+            let text = match state.source {
+                Some(source) => Cow::Borrowed(&source[state.byte..state.byte + len]),
+                None => Cow::Owned(token_chars.iter().cloned().collect()),
+            };
+            state.byte += len;
+            let word = match token {
+                Token::Tracked(_) | Token::Ignored if in_untracked => Word::Untracked(text),
+                Token::Tracked(_) if in_heading => Word::Ignored(text),
+                Token::Untracked => Word::Untracked(text),
+                Token::Ignored => Word::Ignored(text),
+                Token::Tracked(key) => Word::Tracked(text, key, 0.0, None, false),
+            };
+            if c.is_alphabetic() && technical.is_none() && !in_heading {
+                match word {
+                    Word::Ignored(_)
+                        if self.is_proper_noun(token_chars, was_sentence_beginning) => {
+                        state.proper_nouns.push(ast.words.len());
+                    }
+                    Word::Tracked(ref s, _, _, _, _)
+                        if s.chars().next().is_some_and(|c| c.is_lowercase()) => {
+                        state.lowercase_forms.insert(s.to_lowercase());
+                    }
+                    _ => (),
+                }
+            }
+            chars = cs;
+            ast.words.push(word);
+            if is_sentence_beginning && !was_sentence_beginning {
+                ast.mark_sentence();
+            }
+        }
+        state.is_sentence_beginning = is_sentence_beginning;
+        state.in_body = in_body;
+        state.verbatim = verbatim;
+        Ok(v_chars.len() - chars.len())
+    }
+
+    /// Ends tokenizing (see `tokenize_chunk`), and returns the Ast
+    fn tokenize_end<'a>(&mut self, state: TokenizerState<'a>) -> Ast<'a> {
+        let mut ast = state.ast;
+        // Capitalized words that also appear in lowercase are not proper nouns after all
+        for i in state.proper_nouns {
+            let s = match ast.words[i] {
+                Word::Ignored(ref s) => s.clone(),
+                _ => continue,
+            };
+            let key_chars = self.key_chars(&s);
+            let lower_s = key_chars.to_lowercase();
+            if state.lowercase_forms.contains(&lower_s) && !self.ignored.contains(&lower_s) {
+                // Proper nouns are never at the beginning of a sentence
+                let key = self.word_key(&key_chars, false);
+                ast.words[i] = Word::Tracked(s, key, 0.0, None, false);
+            }
+        }
+        if let Some(percentile) = self.adaptive_stoplist {
+            ignore_most_frequent(&mut ast, percentile);
+        }
+        if self.ignore_enumerations {
+            ignore_enumerations(&mut ast);
+        }
+        self.ignore_phrases(&mut ast);
+        self.apply_margins(&mut ast);
+        ast
+    }
+
+    /// Marks the words of the phrases set by `with_ignored_phrases` as ignored
+    fn ignore_phrases(&self, ast: &mut Ast) {
+        if self.ignored_phrases.is_empty() {
+            return;
+        }
+        // Indexes of the words, with the index of the previous word if it is only separated
+        // from it by whitespace
+        let mut words: Vec<(usize, Option<usize>)> = vec![];
+        let mut previous = None;
+        for (i, word) in ast.words.iter().enumerate() {
+            match *word {
+                Word::Untracked(ref s) => {
+                    if !s.chars().all(char::is_whitespace) {
+                        previous = None;
+                    }
+                }
+                _ => {
+                    words.push((i, previous));
+                    previous = Some(i);
+                }
+            }
+        }
+        let mut ignored = vec![];
+        for start in 0..words.len() {
+            for phrase in &self.ignored_phrases {
+                if start + phrase.len() > words.len() {
+                    continue;
+                }
+                let candidate = &words[start..start + phrase.len()];
+                let matches = candidate.iter()
+                                       .zip(phrase)
+                                       .enumerate()
+                                       .all(|(k, (&(i, previous), expected))| {
+                                           (k == 0 || previous == Some(candidate[k - 1].0)) &&
+                                           ast.words[i].as_str().to_lowercase() == *expected
+                                       });
+                if matches {
+                    ignored.extend(candidate.iter().map(|&(i, _)| i));
+                }
+            }
+        }
+        for i in ignored {
+            ast.set_ignored(i);
+        }
+    }
+
+    /// Marks words in the margins set by `with_margins` as ignored
+    fn apply_margins(&self, ast: &mut Ast) {
+        let (head, tail) = self.margins;
+        if head == 0 && tail == 0 {
+            return;
+        }
+        let tracked: Vec<usize> = ast.words
+            .iter()
+            .enumerate()
+            .filter(|&(_, word)| matches!(*word, Word::Tracked(..)))
+            .map(|(i, _)| i)
+            .collect();
+        let n = tracked.len();
+        for (j, &i) in tracked.iter().enumerate() {
+            if j < head || j + tail >= n {
+                ast.set_ignored(i);
+            }
+        }
+    }
+
+
+    /// Detect the local number of repetitions.
+    ///
+    /// For each word, the repetition value is set to the total number of occurences of this word
+    /// since there has been hat least `self.max_distance` between two occurences.
+    ///
+    /// It is the default algorithm, and probably the one you want to use.
+    ///
+    /// # Arguments
+    ///
+    /// `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("Testing whether this repetition detector works or does not work").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast); // not the most useful output format, but the easiest to debug
+    /// assert_eq!(&result, "Testing whether this repetition detector **works** or does not **work**");
+    /// ```
+    pub fn detect_local(&mut self, ast: &mut Ast, threshold: f32) {
+        let scale = self.colour_scale.clone();
+        self.detect_local_with(ast, threshold, |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Detect the local number of repetitions, using a custom function to choose colours.
+    ///
+    /// This is the same algorithm as `detect_local`, but the colour of highlighted words is given
+    /// by `colour_fn`, which takes the repetition value of a word and the threshold as arguments.
+    ///
+    /// # Arguments
+    ///
+    /// `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    /// `colour_fn` – A closure returning the colour of a highlighted word from its value and the threshold
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local_with(&mut ast, 1.9, |x, _| if x > 2.5 { "red" } else { "cyan" });
+    /// let repetitions = parser.ast_to_repetitions(&ast);
+    /// assert_eq!(repetitions.len(), 2);
+    /// assert_eq!(repetitions[0].colour, "cyan");
+    /// ```
+    pub fn detect_local_with<F, C>(&mut self, ast: &mut Ast, threshold: f32, colour_fn: F)
+        where F: Fn(f32, f32) -> C,
+              C: Into<String>
+    {
+        self.detect_local_until(ast,
+                                threshold,
+                                |x, t| colour_fn(x, t).into(),
+                                None,
+                                &HashMap::new());
+    }
+
+    /// Detect the local number of repetitions, stopping early if it takes too long.
+    ///
+    /// This is the same algorithm as `detect_local`, but it stops processing words once
+    /// `budget` has elapsed. In this case, only repetitions in the beginning of the text are
+    /// highlighted, and the state of the parser is reset, so it can be used for another
+    /// detection (but repetitions with words of previous calls will not be detected).
+    ///
+    /// # Arguments
+    ///
+    /// `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    /// `budget` – The maximal time the detection can take
+    ///
+    /// # Returns
+    ///
+    /// `true` if the whole text has been processed, `false` else.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let text = "A word is repeated in a few words. ".repeat(5000);
+    /// let mut ast = parser.tokenize(&text).unwrap();
+    /// assert!(!parser.detect_local_budgeted(&mut ast, 1.9, Duration::from_millis(20)));
+    /// // Words processed before the deadline are highlighted, but not the end of the text
+    /// assert_eq!(ast.words[2].as_str(), "word");
+    /// assert!(ast.words[2].value().is_some_and(|v| v >= 2.0));
+    /// assert!(ast.words[2].colour().is_some());
+    /// assert!(ast.words.last().unwrap().colour().is_none());
+    /// assert_eq!(parser.ast_to_repetitions(&ast).len() % 2, 0);
+    ///
+    /// let mut ast = parser.tokenize("A word is repeated in a few words").unwrap();
+    /// assert!(parser.detect_local_budgeted(&mut ast, 1.9, Duration::new(60, 0)));
+    /// assert_eq!(parser.ast_to_repetitions(&ast).len(), 2);
+    /// ```
+    pub fn detect_local_budgeted(&mut self, ast: &mut Ast, threshold: f32, budget: Duration) -> bool {
+        let scale = self.colour_scale.clone();
+        let deadline = Instant::now() + budget;
+        self.detect_local_until(ast,
+                                threshold,
+                                |x, t| value_to_colour(x, t, &scale),
+                                Some(deadline),
+                                &HashMap::new())
+    }
+
+    /// Detect the local number of repetitions, weighted by the rarity of words.
+    ///
+    /// This is the same algorithm as `detect_local`, but the repetition value of a word is
+    /// multiplied by a weight that is lower for common words, so repetitions of rare words
+    /// are highlighted more readily than repetitions of common ones. The most common words
+    /// of the language have a weight of 0.5, and words that are not in the builtin list of
+    /// common words (currently only for french and english) have a weight of 1.0.
+    ///
+    /// # Arguments
+    ///
+    /// `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("A man, a man, a man and a perspicacious, perspicacious dog").unwrap();
+    /// parser.detect_local_weighted(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "A man, a man, a man and a **perspicacious**, **perspicacious** dog");
+    /// ```
+    pub fn detect_local_weighted(&mut self, ast: &mut Ast, threshold: f32) {
+        let common = Parser::get_common_from_lang(&self.lang);
+        let n = common.len() as f32;
+        let mut weights = HashMap::new();
+        for (rank, word) in common.iter().enumerate() {
+            let key = self.tracking_key(word);
+            // Keep the weight of the most common word if several ones have the same stem
+            weights.entry(key).or_insert(0.5 + 0.5 * rank as f32 / n);
+        }
+        let scale = self.colour_scale.clone();
+        self.detect_local_until(ast,
+                                threshold,
+                                |x, t| value_to_colour(x, t, &scale),
+                                None,
+                                &weights);
+    }
+
+    /// Implementation of `detect_local_with`, stopping at `deadline` if there is one.
+    ///
+    /// Returns `false` if the deadline was reached before all words were processed.
+    fn detect_local_until<F>(&mut self,
+                             ast: &mut Ast,
+                             threshold: f32,
+                             colour_fn: F,
+                             deadline: Option<Instant>,
+                             weights: &HashMap<String, f32>)
+                             -> bool
+        where F: Fn(f32, f32) -> String
+    {
+        let body_start = ast.body_range().start;
+        let sentences = ast.sentence_numbers();
+        let mut vec = ast.get_body_mut();
+
+        // must only be called if offset = 0
+        fn try_remove(pos: u32,
+                      h: &mut HashMap<String, (u32, u32, Vec<usize>)>,
+                      index: &mut LengthIndex,
+                      vec: &[Word],
+                      pos_to_i: &[usize],
+                      max_distance: u32) {
+            if pos > max_distance + 1 {
+                let pos_limit = pos - max_distance;
+                let i = pos_to_i[pos_limit as usize];
+                let stemmed = match vec[i] {
+                    Word::Untracked(_) => panic!("Should not happen"),
+                    Word::Ignored(_) => return,
+                    Word::Tracked(_, ref stemmed, _, _, _) => stemmed,
+                };
+                if let Some(&(old_pos, _, _)) = h.get(stemmed) {
+                    if old_pos == pos_limit + 1 {
+                        h.remove(stemmed);
+                        index_remove(index, stemmed);
+                    }
+                }
+            }
+        }
+        // Position (in characters, starting at 1) of each word, if distance is measured in characters
+        let char_positions: Vec<u32> = if self.distance_unit == DistanceUnit::Characters {
+            let mut position = self.char_offset + 1;
+            vec.iter()
+                .map(|word| {
+                    let p = position;
+                    position += word.as_str().chars().count() as u32;
+                    p
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        for i in 0..vec.len() {
+            // Only check the time every few words, as it is not free either
+            if i % 64 == 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                // Processed words can still be highlighted, but the state is reset
+                self.pos = 1;
+                self.hash.clear();
+                self.fuzzy_index.clear();
+                self.pos_to_i = vec!(0);
+                self.offset = 0;
+                self.sentence_offset = 0;
+                self.char_offset = 0;
+                self.ignore_rare(vec);
+                self.highlight(vec, threshold, colour_fn);
+                return false;
+            }
+            let elem = match vec[i] {
+                Word::Untracked(_) => None,
+                Word::Ignored(_) => {
+                    self.pos += 1;
+                    self.pos_to_i.push(i + self.offset);
+                    None
+                }
+                Word::Tracked(_, ref stemmed, _, _, _) => {
+                    self.pos += 1;
+                    self.pos_to_i.push(i + self.offset);
+                    let s = self.fuzzy_get(&self.fuzzy_index, stemmed);
+                    Some((self.hash.remove(&s), s))
+                }
+            };
+            // Try to remove elements on a map
+            if self.fuzzy.is_some() && self.offset == 0 &&
+               self.distance_unit == DistanceUnit::Words {
+                try_remove(self.pos,
+                           &mut self.hash,
+                           &mut self.fuzzy_index,
+                           vec,
+                           &self.pos_to_i,
+                           self.max_distance);
+            }
+            if let Some((e, stemmed)) = elem {
+                // Update old stemmed to the fuzzy matched one
+                if let Word::Tracked(_, ref mut old, _, _, ref mut fuzzy) = vec[i] {
+                    if *old != stemmed {
+                        *old = stemmed.clone();
+                        *fuzzy = true;
+                    }
+                }
+                if self.fuzzy.is_some() {
+                    index_insert(&mut self.fuzzy_index, &stemmed);
+                }
+                let sentence = self.sentence_offset + sentences[body_start + i];
+                let (p_pos, p_sentence, mut subvec) = e.unwrap_or_default();
+                let close_sentence = match self.max_sentence_distance {
+                    Some(d) => sentence - p_sentence <= d,
+                    None => true,
+                };
+                let pos = match self.distance_unit {
+                    DistanceUnit::Words => self.pos,
+                    DistanceUnit::Characters => char_positions[i],
+                };
+                if p_pos != 0 && pos - p_pos < self.max_distance && close_sentence {
+                    subvec.push(i + self.offset);
+                    let v = subvec.len() as f32 * weights.get(&stemmed).map_or(1.0, |&w| w);
+                    for x in &subvec {
+                        if *x >= self.offset {
+                            vec[*x - self.offset].set_count_unless_highlighted(v);
+                        } 
+                    }
+                    self.hash.insert(stemmed, (pos, sentence, subvec));
+                } else {
+                    subvec = vec![i + self.offset];
+                    self.hash.insert(stemmed, (pos, sentence, subvec));
+                }
+            }
+        }
+        self.offset += vec.len();
+        self.char_offset += vec.iter().map(|word| word.as_str().chars().count() as u32).sum::<u32>();
+        // Next call will start with a new sentence
+        self.sentence_offset += sentences.last().map_or(0, |&n| n + 1);
+        
+        self.ignore_rare(vec);
+        self.highlight(vec, threshold, colour_fn);
+        true
+    }
+
+    /// Resets the value of words that have less than `min_occurrences` occurrences
+    fn ignore_rare(&self, words: &mut [Word]) {
+        if self.min_occurrences <= 1 {
+            return;
+        }
+        let mut counts: HashMap<String, u32> = HashMap::new();
+        for word in words.iter() {
+            if let Word::Tracked(_, ref stemmed, _, _, _) = *word {
+                *counts.entry(stemmed.clone()).or_insert(0) += 1;
+            }
+        }
+        for word in words.iter_mut() {
+            let rare = match *word {
+                Word::Tracked(_, ref stemmed, _, _, _) => counts[stemmed] < self.min_occurrences,
+                _ => false,
+            };
+            if rare {
+                word.set_count_unless_highlighted(0.0);
+            }
+        }
+    }
+
+    /// Detect repetitions of the words that end sentences.
+    ///
+    /// Only the last tracked word of each sentence is considered: its repetition value is the
+    /// number of nearby sentences (within `max_distance` words) that end with the same word,
+    /// so sentences with the same ending are highlighted even if this word is not repeated
+    /// much elsewhere.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `threshold` – The threshold to consider a repetition (e.g. 2.5)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("It was the end. Nobody saw the end. Then, it was the end. \
+    ///                                An end is near.").unwrap();
+    /// parser.detect_sentence_ends(&mut ast, 2.5);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "It was the **end**. Nobody saw the **end**. Then, it was the **end**. \
+    ///                      An end is near.");
+    /// ```
+    pub fn detect_sentence_ends(&self, ast: &mut Ast, threshold: f32) {
+        let range = ast.body_range();
+        let sentences = ast.sentence_numbers();
+
+        // Position (in words), index and stem of the last tracked word of each sentence
+        let mut ends: Vec<(u32, usize, String)> = vec![];
+        let mut pos = 0;
+        let mut last: Option<(u32, usize, String)> = None;
+        let mut current_sentence = None;
+        for i in range.clone() {
+            if current_sentence != Some(sentences[i]) {
+                ends.extend(last.take());
+                current_sentence = Some(sentences[i]);
+            }
+            match ast.words[i] {
+                Word::Untracked(_) => continue,
+                Word::Ignored(_) => (),
+                Word::Tracked(_, ref stemmed, _, _, _) => last = Some((pos, i, stemmed.clone())),
+            }
+            pos += 1;
+        }
+        ends.extend(last.take());
+
+        // stem -> (position of last occurrence, indexes of occurrences)
+        let mut h: HashMap<&str, (u32, Vec<usize>)> = HashMap::new();
+        let vec = &mut ast.words;
+        for &(pos, i, ref stemmed) in &ends {
+            let entry = h.entry(stemmed).or_insert((pos, vec![]));
+            if pos - entry.0 >= self.max_distance {
+                entry.1.clear();
+            }
+            entry.0 = pos;
+            entry.1.push(i);
+            let v = entry.1.len() as f32;
+            for &j in &entry.1 {
+                vec[j].set_count_unless_highlighted(v);
+            }
+        }
+
+        let scale = self.colour_scale.clone();
+        self.highlight(&mut vec[range], threshold, |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Detect local repetitions, using a distance in sentences instead of words.
+    ///
+    /// Two occurrences of a word are counted as a repetition if they are at most `window`
+    /// sentences apart, regardless of the number of words between them: `0` only counts
+    /// repetitions inside the same sentence, `1` also counts those in the next sentence, etc.
+    /// `max_distance` is not used by this method.
+    ///
+    /// Sentences end with `.`, `!` or `?`. Abbreviations such as "M." or "etc." are not
+    /// recognized, so they also end a sentence.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `window` – The max number of sentences between two occurrences of a repetition
+    /// * `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false);
+    /// let mut ast = parser.tokenize("A cat saw a cat! Then a dog came. Why? Nobody knows. \
+    ///                                The dog left.").unwrap();
+    /// parser.detect_local_sentences(&mut ast, 0, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "A **cat** saw a **cat**! Then a dog came. Why? Nobody knows. The dog left.");
+    ///
+    /// let mut ast = parser.tokenize("A cat saw a cat! Then a dog came. Why? Nobody knows. \
+    ///                                The dog left.").unwrap();
+    /// parser.detect_local_sentences(&mut ast, 3, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "A **cat** saw a **cat**! Then a **dog** came. Why? Nobody knows. The **dog** left.");
+    /// ```
+    pub fn detect_local_sentences(&self, ast: &mut Ast, window: u32, threshold: f32) {
+        let range = ast.body_range();
+        let sentences = ast.sentence_numbers();
+        let vec = &mut ast.words;
+
+        // stem -> (sentence of last occurrence, indexes of occurrences)
+        let mut h: HashMap<String, (u32, Vec<usize>)> = HashMap::new();
+        for i in range.clone() {
+            let stemmed = match vec[i] {
+                Word::Tracked(_, ref stemmed, _, _, _) => stemmed.clone(),
+                _ => continue,
+            };
+            let sentence = sentences[i];
+            let entry = h.entry(stemmed).or_insert((sentence, vec![]));
+            if sentence - entry.0 > window {
+                entry.1.clear();
+            }
+            entry.0 = sentence;
+            entry.1.push(i);
+            let v = entry.1.len() as f32;
+            for &j in &entry.1 {
+                vec[j].set_count_unless_highlighted(v);
+            }
+        }
+
+        let scale = self.colour_scale.clone();
+        self.highlight(&mut vec[range], threshold, |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Computes the local repetition value of the word at `index`, without modifying the Ast.
+    ///
+    /// This is the value `detect_local` would give to this word if it was called on this Ast
+    /// (with a new parser with the same options, and without fuzzy matching), before
+    /// highlighting, so this can be used e.g. to preview the effect of inserting a word. As
+    /// in `detect_local`, a word that isn't repeated nearby has a value of 0, as well as a word
+    /// with less occurrences than set by `with_min_occurrences`. Returns 0 if the word at
+    /// `index` is not tracked.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// assert_eq!(parser.value_at(&ast, 2), 2.0);
+    /// assert_eq!(parser.value_at(&ast, 4), 0.0); // "is"
+    /// assert_eq!(parser.value_at(&ast, 1), 0.0); // whitespace
+    /// ```
+    ///
+    /// It gives the same values as `detect_local`:
+    ///
+    /// ```rust
+    /// use caribon::Parser;
+    /// let text = "The cat saw the other cat. A dog, a cat and a dog. Then the cat left, \
+    ///             and a dog came back to see where the cat was.";
+    /// let parsers = vec![Parser::new("english").unwrap(),
+    ///                    Parser::new("english").unwrap().with_max_distance(8),
+    ///                    Parser::new("english").unwrap().with_min_occurrences(4),
+    ///                    Parser::new("english").unwrap().with_max_sentence_distance(Some(0))];
+    /// for mut parser in parsers {
+    ///     let mut ast = parser.tokenize(text).unwrap();
+    ///     let values: Vec<f32> = (0..ast.words.len()).map(|i| parser.value_at(&ast, i)).collect();
+    ///     // With a threshold of 0, no value is reset by highlighting
+    ///     parser.detect_local(&mut ast, 0.0);
+    ///     let detected: Vec<f32> = ast.words.iter().map(|w| w.value().unwrap_or(0.0)).collect();
+    ///     assert_eq!(values, detected);
+    /// }
+    /// ```
+    pub fn value_at(&self, ast: &Ast, index: usize) -> f32 {
+        let target = match ast.words[index] {
+            Word::Tracked(_, ref stemmed, _, _, _) => stemmed,
+            _ => return 0.0,
+        };
+        // Words that are too rare in the whole text are never highlighted
+        if self.min_occurrences > 1 {
+            let occurrences = ast.words[ast.body_range()]
+                .iter()
+                .filter(|word| match **word {
+                    Word::Tracked(_, ref stemmed, _, _, _) => stemmed == target,
+                    _ => false,
+                })
+                .count();
+            if (occurrences as u32) < self.min_occurrences {
+                return 0.0;
+            }
+        }
+        let sentences = ast.sentence_numbers();
+
+        // Number of words in the chain of occurrences that contains `index`, if it has been
+        // seen, and position and sentence of the previous occurrence
+        let mut count = 0;
+        let mut found = false;
+        let mut previous: Option<(u32, u32)> = None;
+        let mut pos = 0;
+        let mut char_pos = 0;
+        for i in ast.body_range() {
+            let word = &ast.words[i];
+            let word_pos = match self.distance_unit {
+                DistanceUnit::Words => pos,
+                DistanceUnit::Characters => char_pos,
+            };
+            char_pos += word.as_str().chars().count() as u32;
+            match *word {
+                Word::Untracked(_) => continue,
+                Word::Ignored(_) => (),
+                Word::Tracked(_, ref stemmed, _, _, _) if stemmed == target => {
+                    let close = match previous {
+                        Some((p_pos, p_sentence)) => {
+                            word_pos - p_pos < self.max_distance &&
+                            self.max_sentence_distance
+                                .is_none_or(|d| sentences[i] - p_sentence <= d)
+                        }
+                        None => false,
+                    };
+                    if !close {
+                        if found {
+                            break;
+                        }
+                        count = 0;
+                    }
+                    count += 1;
+                    found = found || i == index;
+                    previous = Some((word_pos, sentences[i]));
+                }
+                Word::Tracked(..) => (),
+            }
+            pos += 1;
+        }
+        // A word that isn't part of a chain keeps its value of 0
+        if count < 2 {
+            0.0
+        } else {
+            count as f32
+        }
+    }
+
+    /// Detect alliteration, i.e. runs of nearby words beginning with the same letter.
+    ///
+    /// Runs of at least `run` consecutive tracked words starting with the same letter
+    /// (case insensitive) are highlighted. Ignored words (e.g. "a", "of") are skipped and
+    /// don't break a run, but the end of a sentence does.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `run` – The minimal number of words in a run (e.g. 3)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("She bought purple pickled peppers at the market.").unwrap();
+    /// parser.detect_alliteration(&mut ast, 3);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "She bought **purple** **pickled** **peppers** at the market.");
+    ///
+    /// let mut ast = parser.tokenize("It was a quiet morning in the small village.").unwrap();
+    /// parser.detect_alliteration(&mut ast, 3);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "It was a quiet morning in the small village.");
+    /// ```
+    pub fn detect_alliteration(&self, ast: &mut Ast, run: usize) {
+        let range = ast.body_range();
+        let sentences = ast.sentence_numbers();
+        let vec = &mut ast.words;
+
+        // Initial letter, sentence and indexes of the words of the current run
+        let mut current: Option<(char, u32, Vec<usize>)> = None;
+        for i in range.clone() {
+            let initial = match vec[i] {
+                Word::Tracked(ref s, _, _, _, _) => {
+                    match s.chars().next().and_then(|c| c.to_lowercase().next()) {
+                        Some(c) => c,
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            let same = match current {
+                Some((c, sentence, _)) => c == initial && sentence == sentences[i],
+                None => false,
+            };
+            if same {
+                if let Some((_, _, ref mut indexes)) = current {
+                    indexes.push(i);
+                }
+            } else {
+                if let Some((_, _, indexes)) = current.take() {
+                    set_run_count(vec, &indexes);
+                }
+                current = Some((initial, sentences[i], vec![i]));
+            }
+        }
+        if let Some((_, _, indexes)) = current.take() {
+            set_run_count(vec, &indexes);
+        }
+
+        let scale = self.colour_scale.clone();
+        self.highlight(&mut vec[range],
+                       run as f32,
+                       |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Detect overuse of words written in capitals (e.g. "FREE", "NOW").
+    ///
+    /// If the text contains at least `threshold` tracked words written in capitals (with at
+    /// least two letters, so "I" or "A" don't count), they are all highlighted, in purple,
+    /// whether they are repeated or not.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `threshold` – The minimal number of words in capitals for them to be highlighted
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("FREE gift! Call NOW and BUY one today").unwrap();
+    /// parser.detect_allcaps(&mut ast, 3.0);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "**FREE** gift! Call **NOW** and **BUY** one today");
+    ///
+    /// let mut ast = parser.tokenize("A free gift from the NASA shop").unwrap();
+    /// parser.detect_allcaps(&mut ast, 3.0);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "A free gift from the NASA shop");
+    /// ```
+    pub fn detect_allcaps(&self, ast: &mut Ast, threshold: f32) {
+        let range = ast.body_range();
+        let vec = &mut ast.words[range];
+
+        let is_allcaps = |w: &Word| match *w {
+            Word::Tracked(ref s, _, _, _, _) => {
+                s.chars().filter(|c| c.is_alphabetic()).count() >= 2 &&
+                !s.chars().any(|c| c.is_lowercase())
+            }
+            _ => false,
+        };
+        let count = vec.iter().filter(|w| is_allcaps(w)).count() as f32;
+        for word in vec.iter_mut() {
+            let x = if is_allcaps(word) { count } else { 0.0 };
+            word.set_count_unless_highlighted(x);
+        }
+
+        self.highlight(vec, threshold, |_, _| ALLCAPS_COLOUR.to_owned())
+    }
+
+    /// Demote repetitions that seem to be deliberate rhetorical devices.
+    ///
+    /// This should be called after a `detect_*` method. Clauses are delimited by sentences
+    /// and by commas, semicolons and colons; when the same word appears at the same position
+    /// in at least three consecutive clauses (e.g. anaphora, as in "we shall fight on the
+    /// beaches, we shall fight on the landing grounds..."), the repetition value of these
+    /// occurrences is divided by the number of clauses. They stay highlighted only if their
+    /// new value is still above `threshold`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `threshold` – The threshold that was used to detect repetitions
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("We fight on the beaches, we fight in the fields, \
+    ///                                we fight at the gates.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// parser.demote_rhetorical(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "We fight on the beaches, we fight in the fields, we fight at the gates.");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("The cat sleeps. A dog barks at the cat, then the cat \
+    ///                                runs away.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// parser.demote_rhetorical(&mut ast, 1.9);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "The **cat** sleeps. A dog barks at the **cat**, then the **cat** \
+    ///                      runs away.");
+    /// ```
+    pub fn demote_rhetorical(&self, ast: &mut Ast, threshold: f32) {
+        // Whether the word at `pos` in `clause` has this stem
+        fn same(clause: &[(usize, Option<String>)], pos: usize, stem: &str) -> bool {
+            clause.get(pos).and_then(|(_, s)| s.as_ref()).is_some_and(|s| s == stem)
+        }
+
+        let range = ast.body_range();
+        let sentences = ast.sentence_numbers();
+
+        // Index and stem (for tracked words) of the words of each clause
+        let mut clauses: Vec<Vec<(usize, Option<String>)>> = vec![vec![]];
+        let mut current_sentence = None;
+        for i in range {
+            let new_clause = match ast.words[i] {
+                Word::Untracked(ref s) => s.contains([',', ';', ':']),
+                _ => current_sentence.is_some_and(|n| n != sentences[i]),
+            };
+            if new_clause && !clauses.last().unwrap().is_empty() {
+                clauses.push(vec![]);
+            }
+            match ast.words[i] {
+                Word::Untracked(_) => continue,
+                Word::Ignored(_) => clauses.last_mut().unwrap().push((i, None)),
+                Word::Tracked(_, ref stemmed, _, _, _) => {
+                    clauses.last_mut().unwrap().push((i, Some(stemmed.clone())))
+                }
+            }
+            current_sentence = Some(sentences[i]);
+        }
+
+        // Index of the words that are part of a pattern -> number of clauses in the pattern
+        let mut demoted: HashMap<usize, usize> = HashMap::new();
+        for k in 0..clauses.len() {
+            for (pos, (_, stem)) in clauses[k].iter().enumerate() {
+                let stem = match *stem {
+                    Some(ref stem) => stem,
+                    None => continue,
+                };
+                if k > 0 && same(&clauses[k - 1], pos, stem) {
+                    // Already counted from a previous clause
+                    continue;
+                }
+                let mut n = 1;
+                while k + n < clauses.len() && same(&clauses[k + n], pos, stem) {
+                    n += 1;
+                }
+                if n >= RHETORICAL_MIN_CLAUSES {
+                    for clause in &clauses[k..k + n] {
+                        demoted.insert(clause[pos].0, n);
+                    }
+                }
+            }
+        }
+
+        let scale = self.colour_scale.clone();
+        for (i, n) in demoted {
+            if let Word::Tracked(_, _, ref mut v, ref mut option, _) = ast.words[i] {
+                if option.is_some() {
+                    *v /= n as f32;
+                    if *v >= threshold {
+                        *option = Some(value_to_colour(*v, threshold, &scale));
+                    } else {
+                        *v = 0.0;
+                        *option = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Detect repetitions inside each occurrence of an HTML element.
+    ///
+    /// Repetitions are counted separately in each element named `tag` (e.g. "p"), so a word
+    /// used once in many paragraphs is not highlighted, while a word used many times in the
+    /// same paragraph is. The repetition value of a word is the number of its occurrences in
+    /// the element; words outside of these elements are not counted. The values and colours
+    /// given by previous detections are reset.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `tag` – The name of the HTML element, without brackets (case insensitive)
+    /// * `threshold` – The threshold to consider a repetition (e.g. 2.5)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("<p>A cat.</p><p>A cat.</p><p>A dog, a dog, a dog.</p>").unwrap();
+    /// parser.detect_within_element(&mut ast, "p", 2.5);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "<p>A cat.</p><p>A cat.</p><p>A **dog**, a **dog**, a **dog**.</p>");
+    ///
+    /// let mut ast = parser.tokenize("A cat, a cat. <p>A dog, a dog, a dog.</p>").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// parser.detect_within_element(&mut ast, "p", 2.5);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "A cat, a cat. <p>A **dog**, a **dog**, a **dog**.</p>");
+    /// assert!(ast.tracked().filter(|w| w.as_str() == "cat").all(|w| w.value() == Some(0.0)));
+    /// ```
+    pub fn detect_within_element(&self, ast: &mut Ast, tag: &str, threshold: f32) {
+        let vec = ast.get_body_mut();
+        let tag = tag.to_lowercase();
+
+        // Words outside of the elements must not keep the results of a previous detection
+        for word in vec.iter_mut() {
+            if let Word::Tracked(_, _, ref mut v, ref mut option, _) = *word {
+                *v = 0.0;
+                *option = None;
+            }
+        }
+
+        // Sets the value of each word of an element to the number of occurrences of its stem
+        fn count_element(vec: &mut [Word], element: &[usize]) {
+            let mut h: HashMap<String, u32> = HashMap::new();
+            for &i in element {
+                if let Word::Tracked(_, ref stemmed, _, _, _) = vec[i] {
+                    *h.entry(stemmed.clone()).or_insert(0) += 1;
+                }
+            }
+            for &i in element {
+                let n = if let Word::Tracked(_, ref stemmed, _, _, _) = vec[i] {
+                    h[stemmed]
+                } else {
+                    continue;
+                };
+                vec[i].set_count(n as f32);
+            }
+        }
+
+        let mut depth = 0;
+        let mut element = vec![];
+        for i in 0..vec.len() {
+            let name = match vec[i] {
+                Word::Untracked(ref s) => html_tag_name(s),
+                Word::Tracked(..) if depth > 0 => {
+                    element.push(i);
+                    None
+                }
+                _ => None,
+            };
+            if let Some((closing, name)) = name {
+                if name == tag {
+                    if closing && depth > 0 {
+                        depth -= 1;
+                        if depth == 0 {
+                            count_element(vec, &element);
+                            element.clear();
+                        }
+                    } else if !closing {
+                        depth += 1;
+                    }
+                }
+            }
+        }
+        // Unclosed element
+        count_element(vec, &element);
+
+        let scale = self.colour_scale.clone();
+        self.highlight(vec, threshold, |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Returns stats about the words
+    ///
+    /// # Arguments
+    ///
+    /// `words` – A reference to a list of words
+    ///
+    /// # Returns
+    ///
+    /// This method retuns a tuple:
+    /// * the first element is a hashmap between stemmed strings and the number of occurences of this word
+    /// * the second oelement is the total number of (valid) words in the list (non counting whitespace, HTML tags...)
+    pub fn words_stats(&self, ast: &Ast) -> (HashMap<String, f32>, u32) {
+        let mut h: HashMap<String, f32> = HashMap::new();
+        let mut count: u32 = 0;
+        let words: &[Word] = ast.get_body();
+
+        // we fill the map and count
+        for word in words {
+            if let Word::Untracked(_) = *word {
+                continue;
+            }
+            count += 1;
+            if let Some(stemmed) = word.stem() {
+                *h.entry(stemmed.to_owned()).or_insert(0.0) += 1.0;
+            }
+        }
+
+        (h, count)
+    }
+
+    /// Returns statistics about the words of the text.
+    ///
+    /// This contains the same information as `words_stats`, but stems are sorted by their
+    /// number of occurrences (and alphabetically for stems with the same number).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("The cat saw the cat, the dog saw the cat").unwrap();
+    /// let stats = parser.stats_report(&ast);
+    /// assert_eq!(stats.total_words, 10);
+    /// assert_eq!(stats.unique_stems, 3);
+    /// assert_eq!(stats.top, vec![("cat".to_owned(), 3), ("saw".to_owned(), 2),
+    ///                            ("dog".to_owned(), 1)]);
+    /// ```
+    pub fn stats_report(&self, ast: &Ast) -> Stats {
+        let (h, count) = self.words_stats(ast);
+        let mut top: Vec<(String, u32)> = h.into_iter().map(|(k, v)| (k, v as u32)).collect();
+        top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Stats {
+            total_words: count,
+            unique_stems: top.len(),
+            top,
+        }
+    }
+
+    /// Returns the set of all the distinct stems of tracked words, sorted.
+    ///
+    /// Only the body of the document is taken into account.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("Eating apples, eating pears, eating apples").unwrap();
+    /// let stems = parser.distinct_stems(&ast);
+    /// assert_eq!(stems.len(), 3);
+    /// assert_eq!(stems, parser.words_stats(&ast).0.keys().cloned().collect());
+    /// ```
+    pub fn distinct_stems(&self, ast: &Ast) -> BTreeSet<String> {
+        ast.get_body()
+           .iter()
+           .filter_map(|w| match *w {
+               Word::Tracked(_, ref stemmed, _, _, _) => Some(stemmed.clone()),
+               _ => None,
+           })
+           .collect()
+    }
+
+    /// Returns an estimation of the time required to read the text.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to an `Ast`
+    /// * `wpm` – The reading speed, in words per minute
+    ///
+    /// # Panics
+    ///
+    /// Panics if `wpm` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize(&"word ".repeat(200)).unwrap();
+    /// assert_eq!(parser.reading_time(&ast, 200), Duration::from_secs(60));
+    /// ```
+    pub fn reading_time(&self, ast: &Ast, wpm: u32) -> Duration {
+        let (_, count) = self.words_stats(ast);
+        words_to_duration(count, wpm)
+    }
+
+    /// Returns an estimation of the time spent reading highlighted repetitions.
+    ///
+    /// This is the same as `reading_time`, but only counts words that have been
+    /// highlighted by one of the `detect_*` methods.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `wpm` is zero.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::time::Duration;
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(parser.repetitions_reading_time(&ast, 60), Duration::from_secs(2));
+    /// ```
+    pub fn repetitions_reading_time(&self, ast: &Ast, wpm: u32) -> Duration {
+        let count = ast.get_body()
+            .iter()
+            .filter(|word| matches!(**word, Word::Tracked(_, _, _, Some(_), _)))
+            .count();
+        words_to_duration(count as u32, wpm)
+    }
+
+    /// Detect the global number of repetitions.
+    ///
+    /// For each word, repetition value is set to the total number of occurences of this word in whole text,
+    /// divided by total number of words in the text
+    ///
+    /// # Arguments
+    ///
+    /// * `vec` – A vector of `Word`.
+    /// * `threshold` – A threshold to highlight repetitions (e.g. 0.01)
+    pub fn detect_global(&self, ast: &mut Ast, threshold: f32) {
+        let (h, count) = self.words_stats(&ast);
+        let mut vec = ast.get_body_mut();
+
+
+        // If there are not enough words for the threshold, do nothing instead of
+        // underlining all words
+        if 1.0 / threshold >= (count as f32) {
+            return;
+        }
+
+        // We set each word value to the relative number of occurences
+        for i in 0..vec.len() {
+            let tmp = if let Word::Tracked(_, ref stemmed, _, _, _) = vec[i] {
+                let x = h.get(stemmed).expect("HashMap was not filled correctly");
+                Some(*x)
+            } else {
+                None
+            };
+            if let Some(x) = tmp {
+                vec[i].set_count_unless_highlighted(x / (count as f32));
+
+            }
+        }
+        self.highlight(vec, threshold, |_, _| "blue".to_owned())
+    }
+
+    /// Detect repetitions, with a contribution that decays over distance.
+    ///
+    /// Unlike `detect_local`, there is no hard `max_distance` cutoff: each other occurrence
+    /// of a word contributes to the repetition value of an occurrence, but less and less as
+    /// it gets further away. The repetition value of the word at position `p` is
+    /// `Σ leak^|p - q|` for all occurrences `q` of this word (including itself), distances
+    /// being measured in words (see `with_leak`). E.g., with the default leak of 0.98, two
+    /// occurrences separated by 5 words both get a value of 1 + 0.98⁵ ≈ 1.90.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `threshold` – The threshold to consider a repetition (e.g. 1.9)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let text = "It is very very good, but the dog saw a dog.";
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "It is **very** **very** good, but the **dog** saw a **dog**.");
+    ///
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_leak(0.9);
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// parser.detect_leak(&mut ast, 1.9);
+    /// // 1 + 0.9 for "very", but only 1 + 0.9³ for "dog"
+    /// assert_eq!(&parser.ast_to_markdown(&ast),
+    ///            "It is **very** **very** good, but the dog saw a dog.");
+    /// ```
+    pub fn detect_leak(&self, ast: &mut Ast, threshold: f32) {
+        let vec = ast.get_body_mut();
+
+        // Position (in words) of each tracked word, with its index
+        let mut tracked: Vec<(usize, i32)> = vec![];
+        let mut pos = 0;
+        for (i, word) in vec.iter().enumerate() {
+            match *word {
+                Word::Untracked(_) => continue,
+                Word::Ignored(_) => (),
+                Word::Tracked(..) => tracked.push((i, pos)),
+            }
+            pos += 1;
+        }
+
+        // Sum of the decayed contributions of the previous (or next) occurrences, with the
+        // position of the last one, in each direction
+        let mut values = vec![0.0; vec.len()];
+        for &forward in &[true, false] {
+            let order: Vec<&(usize, i32)> = if forward {
+                tracked.iter().collect()
+            } else {
+                tracked.iter().rev().collect()
+            };
+            let mut h: HashMap<&str, (f32, i32)> = HashMap::new();
+            for &&(i, pos) in &order {
+                if let Word::Tracked(_, ref stemmed, _, _, _) = vec[i] {
+                    let entry = h.entry(stemmed).or_insert((0.0, pos));
+                    entry.0 = entry.0 * self.leak.powi((pos - entry.1).abs()) + 1.0;
+                    entry.1 = pos;
+                    values[i] += entry.0;
+                }
+            }
+        }
+        for &(i, _) in &tracked {
+            // The word itself has been counted in both directions
+            vec[i].set_count_unless_highlighted(values[i] - 1.0);
+        }
+
+        let scale = self.colour_scale.clone();
+        self.highlight(vec, threshold, |x, t| value_to_colour(x, t, &scale))
+    }
+
+    /// Suggests a threshold for `detect_local` or `detect_global`, depending on the text.
+    ///
+    /// For local detection, the suggested threshold is 1.9 (i.e. a word is highlighted as soon as it
+    /// is repeated), unless more than a quarter of the words are repeated within `max_distance`,
+    /// in which case it is 2.9 so only the most obvious repetitions are highlighted. For global
+    /// detection, it is the relative frequency of the fifth most frequent word, but at least 0.01.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to an `Ast` returned by `tokenize`
+    /// * `global` – Whether the threshold is for `detect_global` instead of `detect_local`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// assert_eq!(parser.suggest_threshold(&ast, false), 1.9);
+    /// let ast = parser.tokenize("Words, words, words").unwrap();
+    /// assert_eq!(parser.suggest_threshold(&ast, false), 2.9);
+    /// ```
+    pub fn suggest_threshold(&self, ast: &Ast, global: bool) -> f32 {
+        if global {
+            let (h, count) = self.words_stats(ast);
+            let mut frequencies: Vec<f32> = h.values().map(|&x| x / count as f32).collect();
+            frequencies.sort_by(|a, b| b.partial_cmp(a).unwrap());
+            frequencies.get(4).map_or(0.01, |&x| x.max(0.01))
+        } else {
+            // stem -> position of last occurrence
+            let mut last: HashMap<&str, u32> = HashMap::new();
+            let mut pos = 0;
+            let mut tracked = 0;
+            let mut repeated = 0;
+            for word in ast.get_body() {
+                match *word {
+                    Word::Untracked(_) => continue,
+                    Word::Ignored(_) => (),
+                    Word::Tracked(_, ref stemmed, _, _, _) => {
+                        tracked += 1;
+                        if let Some(p) = last.insert(stemmed, pos) {
+                            if pos - p < self.max_distance {
+                                repeated += 1;
+                            }
+                        }
+                    }
+                }
+                pos += 1;
+            }
+            if repeated * 4 > tracked { 2.9 } else { 1.9 }
+        }
+    }
+
+    /// Suggests a max distance for `detect_local` (see `with_max_distance`), depending on the text.
+    ///
+    /// The suggested distance is three times the mean length (in words) of the sentences of the
+    /// text, so that repetitions are detected in about three consecutive sentences. It is
+    /// bounded between 10 and 200 words. If the text contains no word, the current max distance
+    /// is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("Short one. Another one. And a third one.").unwrap();
+    /// let short = parser.suggest_max_distance(&ast);
+    /// let ast = parser.tokenize("This sentence is quite a lot longer than the previous ones, \
+    ///                            and it goes on and on for a while before it ends. This one \
+    ///                            is also rather long, as it contains many words that are not \
+    ///                            really needed.").unwrap();
+    /// let long = parser.suggest_max_distance(&ast);
+    /// assert_eq!(short, 10);
+    /// assert!(long > short);
+    /// ```
+    pub fn suggest_max_distance(&self, ast: &Ast) -> u32 {
+        let range = ast.body_range();
+        let sentences = ast.sentence_numbers();
+        let mut n_words = 0;
+        let mut n_sentences = HashSet::new();
+        for i in range {
+            match ast.words[i] {
+                Word::Untracked(_) => (),
+                _ => {
+                    n_words += 1;
+                    n_sentences.insert(sentences[i]);
+                }
+            }
+        }
+        if n_sentences.is_empty() {
+            return self.max_distance;
+        }
+        let mean = n_words as f32 / n_sentences.len() as f32;
+        ((3.0 * mean).round() as u32).clamp(10, 200)
+    }
+
+    /// Returns a sparkline showing the density of repetitions across the document.
+    ///
+    /// The words of the text (tracked or ignored) are split into `buckets` segments of
+    /// (roughly) the same length, and for each segment the proportion of highlighted words is
+    /// displayed with a block (from '▁' to '█'), relatively to the densest segment. This
+    /// should be called after a `detect_*` method.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    /// * `buckets` – The number of segments, i.e. of characters of the sparkline
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("The cat and the cat and the cat. It was a quiet morning \
+    ///                                in a small village near a river.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.density_sparkline(&ast, 2), "█▁");
+    /// ```
+    pub fn density_sparkline(&self, ast: &Ast, buckets: usize) -> String {
+        const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        if buckets == 0 {
+            return String::new();
+        }
+        let words: Vec<&Word> = ast.get_body()
+            .iter()
+            .filter(|word| !matches!(**word, Word::Untracked(_)))
+            .collect();
+
+        // Number of words and of highlighted words in each bucket
+        let mut counts = vec![(0, 0); buckets];
+        for (k, word) in words.iter().enumerate() {
+            let bucket = &mut counts[k * buckets / words.len()];
+            bucket.0 += 1;
+            if let Word::Tracked(_, _, _, Some(_), _) = **word {
+                bucket.1 += 1;
+            }
+        }
+        let densities: Vec<f32> = counts.iter()
+            .map(|&(n, highlighted)| if n == 0 { 0.0 } else { highlighted as f32 / n as f32 })
+            .collect();
+        let max = densities.iter().fold(0.0, |max: f32, &d| max.max(d));
+        densities.iter()
+            .map(|&d| if max > 0.0 { BLOCKS[(d / max * 7.0).round() as usize] } else { BLOCKS[0] })
+            .collect()
+    }
+
+    /// Detect the local number of repetitions, with an optional threshold.
+    ///
+    /// This is the same as `detect_local`, but if `threshold` is `None`, the threshold
+    /// given by `suggest_threshold` is used.
+    ///
+    /// # Returns
+    ///
+    /// The threshold that was used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (lang, text) = caribon::examples()[0];
+    /// let mut parser = caribon::Parser::new(lang).unwrap();
+    /// let mut ast = parser.tokenize(text).unwrap();
+    /// assert_eq!(parser.detect_local_auto(&mut ast, None), 1.9);
+    /// let repetitions = parser.ast_to_repetitions(&ast).len();
+    /// assert!(repetitions > 0 && repetitions < 20);
+    /// ```
+    pub fn detect_local_auto(&mut self, ast: &mut Ast, threshold: Option<f32>) -> f32 {
+        let threshold = threshold.unwrap_or_else(|| self.suggest_threshold(ast, false));
+        self.detect_local(ast, threshold);
+        threshold
+    }
+
+    /// Detect local repetitions, choosing the threshold so that roughly `percentile` percent
+    /// of the tracked words are highlighted.
+    ///
+    /// Repetition values are first computed as with `detect_local`, then the threshold is set
+    /// to the value of the word at this percentile, so more words can be highlighted if several
+    /// ones have the same value. The threshold is at least 2, so words that are not repeated
+    /// are never highlighted. Returns the threshold that was used.
+    ///
+    /// Words that have already been highlighted by a previous detection are kept as they
+    /// are, and are not taken into account to compute the percentile.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `percentile` – The percentage (between 0 and 100) of tracked words to highlight
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// // 90 different words, and a word repeated 10 times
+    /// let letters: Vec<char> = "bcdfghjklmnpqrtvwxz".chars().collect();
+    /// let mut words: Vec<String> = letters.iter()
+    ///     .flat_map(|&a| letters.iter().map(move |&b| format!("{}{}", a, b)))
+    ///     .take(90)
+    ///     .collect();
+    /// for i in 0..10 {
+    ///     words.insert(2 * i, "apple".to_owned());
+    /// }
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize(&words.join(" ")).unwrap();
+    /// assert_eq!(parser.detect_local_percentile(&mut ast, 10.0), 10.0);
+    /// let highlighted: Vec<_> = ast.tracked().filter(|word| word.colour().is_some()).collect();
+    /// assert_eq!(highlighted.len(), 10);
+    /// assert!(highlighted.iter().all(|word| word.as_str() == "apple"));
+    ///
+    /// // Highlights of previous detections are kept
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize(&words.join(" ")).unwrap();
+    /// parser.detect_global(&mut ast, 0.02);
+    /// parser.detect_local_percentile(&mut ast, 5.0);
+    /// assert_eq!(ast.tracked().filter(|word| word.colour() == Some("blue")).count(), 10);
+    ///
+    /// // The values used to compute the threshold are not limited by `with_max_highlights`
+    /// for i in 0..5 {
+    ///     words.insert(4 * i + 1, "pear".to_owned());
+    /// }
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_max_highlights(1);
+    /// let mut ast = parser.tokenize(&words.join(" ")).unwrap();
+    /// assert_eq!(parser.detect_local_percentile(&mut ast, 14.0), 5.0);
+    /// let highlighted: Vec<_> = ast.tracked().filter(|word| word.colour().is_some()).collect();
+    /// assert!(highlighted.iter().all(|word| word.as_str() == "apple"));
+    /// ```
+    pub fn detect_local_percentile(&mut self, ast: &mut Ast, percentile: f32) -> f32 {
+        // Words highlighted by a previous detection are left as they are
+        let highlighted: Vec<bool> = ast.get_body().iter().map(|w| w.colour().is_some()).collect();
+
+        // Highlights all other tracked words, so their values are kept (the limit on the
+        // number of highlights only applies to the final pass)
+        let max_highlights = self.max_highlights.take();
+        self.detect_local_with(ast, 0.0, |_, _| "");
+        self.max_highlights = max_highlights;
+
+        let mut values = vec![];
+        for (word, &highlighted) in ast.get_body_mut().iter_mut().zip(&highlighted) {
+            if highlighted {
+                continue;
+            }
+            if let Word::Tracked(_, _, v, ref mut colour, _) = *word {
+                values.push(v);
+                *colour = None;
+            }
+        }
+        values.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let n = (percentile / 100.0 * values.len() as f32).ceil() as usize;
+        let threshold = match n {
+            0 => f32::INFINITY,
+            n => values[n.min(values.len()) - 1],
+        };
+        let threshold = threshold.max(2.0);
+
+        let scale = self.colour_scale.clone();
+        self.highlight(ast.get_body_mut(), threshold, |x, t| value_to_colour(x, t, &scale));
+        threshold
+    }
+
+    /// Detect the global number of repetitions, with an optional threshold.
+    ///
+    /// This is the same as `detect_global`, but if `threshold` is `None`, the threshold
+    /// given by `suggest_threshold` is used.
+    ///
+    /// # Returns
+    ///
+    /// The threshold that was used.
+    pub fn detect_global_auto(&self, ast: &mut Ast, threshold: Option<f32>) -> f32 {
+        let threshold = threshold.unwrap_or_else(|| self.suggest_threshold(ast, true));
+        self.detect_global(ast, threshold);
+        threshold
+    }
+
+    /// Detect collocations, i.e. pairs of different words that occur together too often.
+    ///
+    /// Two words are considered to co-occur if there are at most `window` tracked words
+    /// between them (so `window = 1` only considers adjacent words). For each word, the
+    /// repetition value is set to the number of co-occurrences of the most frequent pair
+    /// it belongs to; words whose value reaches `threshold` are highlighted in purple.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A mutable reference to an internal data structure returned by `tokenize`
+    /// * `window` – The maximal distance (in tracked words) between the two words of a pair
+    /// * `threshold` – The minimal number of co-occurrences of a pair to highlight its words
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("I drink strong coffee. You like strong coffee. We want strong coffee.").unwrap();
+    /// parser.detect_collocations(&mut ast, 2, 3);
+    /// let result = parser.ast_to_markdown(&ast);
+    /// assert_eq!(&result, "I drink **strong** **coffee**. You like **strong** **coffee**. We want **strong** **coffee**.");
+    /// ```
+    pub fn detect_collocations(&self, ast: &mut Ast, window: usize, threshold: u32) {
+        let vec = ast.get_body_mut();
+
+        // Indexes and stems of tracked words
+        let tracked: Vec<(usize, String)> = vec.iter()
+            .enumerate()
+            .filter_map(|(i, word)| match *word {
+                Word::Tracked(_, ref stemmed, _, _, _) => Some((i, stemmed.clone())),
+                _ => None,
+            })
+            .collect();
+
+        // Returns the pair key, with stems in a canonical order
+        fn pair_key<'a>(a: &'a str, b: &'a str) -> (&'a str, &'a str) {
+            if a < b { (a, b) } else { (b, a) }
+        }
+
+        // We count all pairs in the window
+        let mut pairs: HashMap<(&str, &str), u32> = HashMap::new();
+        for j in 0..tracked.len() {
+            for k in j + 1..tracked.len().min(j + window + 1) {
+                if tracked[j].1 != tracked[k].1 {
+                    *pairs.entry(pair_key(&tracked[j].1, &tracked[k].1)).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // Each word gets the count of its most frequent pair
+        let mut values = vec![0; tracked.len()];
+        for j in 0..tracked.len() {
+            for k in j + 1..tracked.len().min(j + window + 1) {
+                if let Some(&n) = pairs.get(&pair_key(&tracked[j].1, &tracked[k].1)) {
+                    values[j] = values[j].max(n);
+                    values[k] = values[k].max(n);
+                }
+            }
+        }
+        for (&(i, _), &n) in tracked.iter().zip(values.iter()) {
+            vec[i].set_count_unless_highlighted(n as f32);
+        }
+
+        self.highlight(vec, threshold as f32, |_, _| "purple".to_owned())
+    }
+
+    /// Lists the stems that fuzzy string matching would merge with a given ratio.
+    ///
+    /// The text is tokenized with the same options as this parser, and each pair contains
+    /// the stem that was met first and the (different) stem that fuzzy matching considers
+    /// identical to it. This allows to choose a fuzzy ratio without comparing the full
+    /// outputs. This parser is not modified, so it can still be used to detect repetitions.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` – The input text
+    /// * `fuzzy` – The fuzzy ratio to test (see `with_fuzzy`)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let parser = caribon::Parser::new("english").unwrap();
+    /// let text = "The highlight of the day was this higlight. Trust Rust.";
+    /// assert!(parser.fuzzy_diff(text, 0.1).unwrap().is_empty());
+    ///
+    /// let pairs = parser.fuzzy_diff(text, 0.25).unwrap();
+    /// assert_eq!(pairs.len(), 2);
+    /// assert_eq!(&pairs[0].1, "higlight");
+    /// ```
+    pub fn fuzzy_diff(&self, text: &str, fuzzy: f32) -> Result<Vec<(String, String)>> {
+        let (pairs, _) = try!(self.fuzzy_merges(text, fuzzy));
+        Ok(pairs)
+    }
+
+    /// Returns a warning if the fuzzy ratio set by `with_fuzzy` is too high for a text.
+    ///
+    /// If fuzzy string matching would merge more than 10% of the distinct stems of the text,
+    /// it probably merges words that have nothing in common, and the result will contain a
+    /// lot of false positives. In this case, this method returns `Ok(Some(message))`; else, or
+    /// if fuzzy string matching is not activated, it returns `Ok(None)`. Values between 0.1 and
+    /// 0.25 are usually reasonable; `with_fuzzy_max_distance` can also be used to limit merges.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let text = "The cat sat on the mat with a hat and a bat, then a rat ran.";
+    /// let parser = caribon::Parser::new("english").unwrap()
+    ///                                         .with_fuzzy(Some(0.2));
+    /// assert!(parser.fuzzy_warning(text).unwrap().is_none());
+    ///
+    /// let parser = parser.with_fuzzy(Some(0.9));
+    /// assert!(parser.fuzzy_warning(text).unwrap().is_some());
+    /// ```
+    pub fn fuzzy_warning(&self, text: &str) -> Result<Option<String>> {
+        let fuzzy = match self.fuzzy {
+            Some(fuzzy) => fuzzy,
+            None => return Ok(None),
+        };
+        let (pairs, n) = try!(self.fuzzy_merges(text, fuzzy));
+        if pairs.len() * 10 > n {
+            Ok(Some(format!("Fuzzy string matching with a ratio of {} merges {} of the {} distinct \
+                             words of the text; a lower value (e.g. between 0.1 and 0.25) is \
+                             recommended",
+                            fuzzy,
+                            pairs.len(),
+                            n)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the pairs of stems that fuzzy matching merges with a given ratio, and the
+    /// number of distinct stems of the text
+    fn fuzzy_merges(&self, text: &str, fuzzy: f32) -> Result<(Vec<(String, String)>, usize)> {
+        // Tokenizing needs a mutable stemmer, so we use a new parser with the same options
+        // (the synonym provider is not part of the configuration, but it is not used when
+        // tokenizing or matching stems)
+        let mut parser = try!(Parser::from_config(self.export_config()));
+        parser.fuzzy = Some(fuzzy);
+        let ast = try!(parser.tokenize(text));
+
+        // Stems that are kept as keys, as in detect_local, and stems that have been seen
+        let mut keys: LengthIndex = BTreeMap::new();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut res = vec![];
+        for word in ast.get_body() {
+            if let Word::Tracked(_, ref stemmed, _, _, _) = *word {
+                if !seen.insert(stemmed) {
+                    continue;
+                }
+                let key = parser.fuzzy_get(&keys, stemmed);
+                if &key == stemmed {
+                    index_insert(&mut keys, &key);
+                } else {
+                    res.push((key, stemmed.clone()));
+                }
+            }
+        }
+        Ok((res, seen.len()))
+    }
+
+    /// Highlight words whose value is superior to the threshold
+    ///
+    /// # Arguments
+    ///
+    /// * `words` – A vector containing repetitions.
+    /// * `threshold` – The threshold above which words must be highlighted.
+    /// * `f` – A closure that returns the colour given the value and threshold
+    ///
+    /// # Returns
+    ///
+    /// A vector of highlight
+    fn highlight<F>(&self, words: &mut [Word], threshold: f32, f: F)
+        where F: Fn(f32, f32) -> String
+    {
+        // Whether a word can be replaced, cached so the provider is only called once per word
+        let mut replaceable: HashMap<String, bool> = HashMap::new();
+        let mut res = words;
+        // Indices of the words that are above the threshold and haven't been highlighted yet
+        let mut candidates: Vec<usize> = vec![];
+        for (i, word) in res.iter().enumerate() {
+            if let Word::Tracked(ref s, _, v, None, _) = *word {
+                let keep = match self.synonyms {
+                    Some(ref provider) if self.only_replaceable && v >= threshold => {
+                        let lower = s.to_lowercase();
+                        *replaceable.entry(lower.clone())
+                            .or_insert_with(|| !provider(&lower).is_empty())
+                    }
+                    _ => true,
+                };
+                if v >= threshold && keep {
+                    candidates.push(i);
+                }
+            }
+        }
+        // Only keep the stems with the strongest repetitions (and all the ties at the cutoff)
+        let mut strongest: HashMap<&str, f32> = HashMap::new();
+        if self.max_highlights.is_some() {
+            for &i in &candidates {
+                if let Word::Tracked(_, ref stemmed, v, _, _) = res[i] {
+                    let max = strongest.entry(stemmed).or_insert(v);
+                    *max = max.max(v);
+                }
+            }
+        }
+        let cutoff = match self.max_highlights {
+            Some(n) if strongest.len() > n => {
+                let mut values: Vec<f32> = strongest.values().cloned().collect();
+                values.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+                if n == 0 { f32::INFINITY } else { values[n - 1] }
+            }
+            _ => threshold,
+        };
+        let mut selected = vec![false; res.len()];
+        for i in candidates {
+            if let Word::Tracked(_, ref stemmed, v, _, _) = res[i] {
+                selected[i] = strongest.get(stemmed.as_str()).map_or(v, |&max| max) >= cutoff;
+            }
+        }
+        for i in 0..res.len() {
+            let word: &mut Word = &mut res[i];
+            if let Word::Tracked(_, _, ref mut v, ref mut option, _) = *word {
+                if option.is_none() {
+                    if selected[i] {
+                        // No colour is attributed, so see if we attribute one
+                        *option = Some(f(*v, threshold));
+                    } else {
+                        *v = 0.0; //resets value to zero so it doesn't cause problem if another algorithm is used after
+                    }
+                }
+            }
+        }
+    }
+
+    /// Display the words to terminal, higlighting the repetitions.
+    ///
+    /// Use terminal colour codes to highlight the repetitions
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    pub fn ast_to_terminal(&self, ast: &Ast) -> String {
+        let mut res = String::new();
+        let words = &ast.words;
+
+        for word in words {
+            match *word {
+                Word::Untracked(ref s) => res = res + s,
+                Word::Ignored(ref s) => res = res + s,
+                Word::Tracked(ref s, _, _, ref option, fuzzy) => {
+                    if let Some(ref colour) = *option {
+                        match self.shell_colour(colour) {
+                            None => res = res + s,
+                            Some(shell_colour) if fuzzy && self.mark_fuzzy => {
+                                res = res + SHELL_ITALIC + shell_colour + s + SHELL_COLOUR_OFF
+                            }
+                            Some(shell_colour) => res = res + shell_colour + s + SHELL_COLOUR_OFF,
+                        }
+                    } else {
+                        res.push_str(s);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Display the words to terminal, higlighting the repetitions with 24-bit colours.
+    ///
+    /// Instead of the named colour attributed by `detect_*`, a highlighted word is displayed
+    /// on a gradient going from green to red, through yellow, according to the multiple of
+    /// the threshold that selected its colour in the colour scale (see `with_colour_scale`):
+    /// green for the lowest multiple, red for the highest one. The terminal must support
+    /// truecolor.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("one two one two one").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let result = parser.ast_to_terminal_truecolor(&ast);
+    /// // "one" is above 1.5 times the threshold (orange), "two" only above it (green)
+    /// assert!(result.contains("\x1B[38;2;255;255;0mone\x1B[0m"));
+    /// assert!(result.contains("\x1B[38;2;0;255;0mtwo\x1B[0m"));
+    /// ```
+    pub fn ast_to_terminal_truecolor(&self, ast: &Ast) -> String {
+        let mut res = String::new();
+        for word in &ast.words {
+            match *word {
+                Word::Tracked(ref s, _, _, Some(ref colour), _) => {
+                    res.push_str(&get_truecolor_shell_colour(self.gradient_position(colour)));
+                    res.push_str(s);
+                    res.push_str(SHELL_COLOUR_OFF);
+                }
+                _ => res.push_str(word.as_str()),
+            }
+        }
+        res
+    }
+
+    /// Returns a flat list of all the tokens of the AST.
+    ///
+    /// There is exactly one `TokenInfo` for each element of `ast.words`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::{Parser, WordKind};
+    /// let mut parser = Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("The cat").unwrap();
+    /// let tokens = parser.tokens(&ast);
+    /// assert_eq!(tokens.len(), ast.words.len());
+    /// let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+    /// assert_eq!(kinds, vec![WordKind::Ignored, WordKind::Untracked, WordKind::Tracked]);
+    /// assert_eq!(tokens[2].stem, Some("cat".to_owned()));
+    /// ```
+    pub fn tokens(&self, ast: &Ast) -> Vec<TokenInfo> {
+        ast.words
+            .iter()
+            .map(|word| match *word {
+                Word::Untracked(ref s) => TokenInfo {
+                    text: s.to_string(),
+                    kind: WordKind::Untracked,
+                    stem: None,
+                    colour: None,
+                },
+                Word::Ignored(ref s) => TokenInfo {
+                    text: s.to_string(),
+                    kind: WordKind::Ignored,
+                    stem: None,
+                    colour: None,
+                },
+                Word::Tracked(ref s, ref stemmed, _, ref colour, _) => TokenInfo {
+                    text: s.to_string(),
+                    kind: WordKind::Tracked,
+                    stem: Some(stemmed.clone()),
+                    colour: colour.clone(),
+                },
+            })
+            .collect()
+    }
+
+    /// Returns a list of repetitions found in the AST.
+    ///
+    /// Sorting them by `Repetition::nearest` allows to see the most jarring repetitions first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("One cat, two cats, a dog and three cats").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let repetitions = parser.ast_to_repetitions(&ast);
+    /// let nearest: Vec<_> = repetitions.iter().map(|r| r.nearest()).collect();
+    /// assert_eq!(nearest, vec![Some(2), Some(2), Some(5)]);
+    /// ```
+    pub fn ast_to_repetitions(&self, ast: &Ast) -> Vec<Repetition> {
+        let mut res = vec![];
+        let words = &ast.words;
+        let nearest = nearest_distances(words);
+        let mut pos = 0;
+
+        for (i, word) in words.iter().enumerate() {
+            match *word {
+                Word::Untracked(ref s) => pos += s.chars().count(),
+                Word::Ignored(ref s) => pos += s.chars().count(),
+                Word::Tracked(ref s, _, v, ref highlight, _) => {
+                    let len = s.chars().count();
+                    if let Some(ref colour) = *highlight {
+                        res.push(Repetition {
+                            offset: pos,
+                            length: len,
+                            value: v,
+                            colour: colour.clone(),
+                            nearest: nearest[i],
+                        });
+                    }
+                    pos += len;
+                }
+            }
+        }
+        
+        res
+    }
+
+    /// Returns a patch (in unified diff format) replacing repeated words in the source text.
+    ///
+    /// This should be called after a `detect_*` method. Highlighted occurrences of a word form a
+    /// chain until an occurrence is not highlighted or is further than the maximum distance (see
+    /// `with_max_distance`) from the previous one. In each chain, the first occurrence is kept,
+    /// and the next ones are replaced by the first synonym given by the synonym provider (see
+    /// `with_synonyms`), or by `<REPHRASE>` if there is none. Synonyms that span several lines
+    /// are not used.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    /// * `source` – The text that was given to `tokenize`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let source = "The cat sleeps.\nThe dog barks.\nThe cat wakes up.\n";
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize(source).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.suggest_patch(&ast, source),
+    ///            "--- original\n+++ suggested\n@@ -1,3 +1,3 @@\n The cat sleeps.\n The dog barks.\n\
+    ///             -The cat wakes up.\n+The <REPHRASE> wakes up.\n");
+    ///
+    /// let parser = parser.with_synonyms(|word| match word {
+    ///     "cat" => vec!["feline".to_owned()],
+    ///     _ => vec![],
+    /// });
+    /// assert!(parser.suggest_patch(&ast, source).contains("\n+The feline wakes up.\n"));
+    /// ```
+    pub fn suggest_patch(&self, ast: &Ast, source: &str) -> String {
+        let source_chars: Vec<char> = source.chars().collect();
+        let mut new_text = String::with_capacity(source.len());
+        // stem -> position of its last occurrence, and whether it was highlighted
+        let mut last: HashMap<&str, (usize, bool)> = HashMap::new();
+        let mut pos = 0;
+        let mut n_words = 0;
+        for word in &ast.words {
+            let s = word.as_str();
+            let len = s.chars().count();
+            let position = match self.distance_unit {
+                DistanceUnit::Words => n_words,
+                DistanceUnit::Characters => pos,
+            };
+            let replacement = match *word {
+                Word::Tracked(ref s, ref stemmed, _, ref colour, _) => {
+                    let highlighted = colour.is_some();
+                    let in_chain = match last.insert(stemmed, (position, highlighted)) {
+                        Some((p, true)) => position - p <= self.max_distance as usize,
+                        _ => false,
+                    };
+                    if !highlighted || !in_chain || s.contains('\n') {
+                        None
+                    } else {
+                        let lower = s.to_lowercase();
+                        let synonym = self.synonyms
+                            .as_ref()
+                            .and_then(|provider| {
+                                provider(&lower).into_iter().find(|synonym| !synonym.contains('\n'))
+                            });
+                        Some(match synonym {
+                            Some(synonym) => {
+                                // Keep the initial capital, if any
+                                if s.chars().next().is_some_and(|c| c.is_uppercase()) {
+                                    let mut chars = synonym.chars();
+                                    chars.next()
+                                        .map_or(String::new(), |c| c.to_uppercase().chain(chars).collect())
+                                } else {
+                                    synonym
+                                }
+                            }
+                            None => "<REPHRASE>".to_owned(),
+                        })
+                    }
+                }
+                _ => None,
+            };
+            match *word {
+                Word::Untracked(_) => (),
+                _ => n_words += 1,
+            }
+            let matches_source = pos + len <= source_chars.len() &&
+                                 source_chars[pos..pos + len].iter().cloned().eq(s.chars());
+            match replacement {
+                Some(ref r) if matches_source => new_text.push_str(r),
+                _ => new_text.push_str(s),
+            }
+            pos += len;
+        }
+        unified_diff(source, &new_text)
+    }
+
+    /// Returns the words that are used several times, with their occurrences.
+    ///
+    /// This should be called after a `detect_*` method. There is an entry for each stem that
+    /// appears at least twice in the body of the text (whether it has been highlighted or not),
+    /// ordered by first occurrence.
+    ///
+    /// For each occurrence, the distance to the nearest other one is also given, so the most
+    /// jarring repetitions (e.g. two occurrences only a few words apart) can be found.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let repetitions = parser.repetitions(&ast);
+    /// assert_eq!(repetitions.len(), 1);
+    /// assert_eq!(repetitions[0].positions, vec![2, 14]);
+    /// assert_eq!(repetitions[0].distances, vec![6, 6]);
+    /// assert_eq!(repetitions[0].value, 2.0);
+    /// assert!(repetitions[0].highlighted);
+    /// ```
+    pub fn repetitions(&self, ast: &Ast) -> Vec<RepetitionGroup> {
+        let mut res: Vec<RepetitionGroup> = vec![];
+        // stem -> index of its entry in res
+        let mut entries: HashMap<&str, usize> = HashMap::new();
+        let nearest = nearest_distances(&ast.words);
+        for i in ast.body_range() {
+            if let Word::Tracked(_, ref stemmed, v, ref colour, _) = ast.words[i] {
+                let n = *entries.entry(stemmed).or_insert_with(|| {
+                    res.push(RepetitionGroup {
+                        stemmed: stemmed.clone(),
+                        positions: vec![],
+                        distances: vec![],
+                        value: 0.0,
+                        highlighted: false,
+                    });
+                    res.len() - 1
+                });
+                let entry = &mut res[n];
+                entry.positions.push(i);
+                entry.distances.push(nearest[i].unwrap_or(0));
+                entry.value = entry.value.max(v);
+                entry.highlighted = entry.highlighted || colour.is_some();
+            }
+        }
+        res.retain(|entry| entry.positions.len() > 1);
+        res
+    }
+
+    /// Returns the text around each occurrence of the highlighted words.
+    ///
+    /// This should be called after a `detect_*` method. There is an entry for each stem that
+    /// has been highlighted at least once, ordered by first occurrence (as in `repetitions`),
+    /// with a snippet for each of its occurrences. Whitespace is collapsed in the snippets,
+    /// and HTML tags are not included.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    /// * `radius` – The number of words to include before and after each occurrence
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words, sadly.").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let contexts = parser.repetition_contexts(&ast, 2);
+    /// assert_eq!(contexts, vec![("word".to_owned(),
+    ///                            vec!["This word is repeated".to_owned(),
+    ///                                 "a few words, sadly.".to_owned()])]);
+    /// ```
+    pub fn repetition_contexts(&self, ast: &Ast, radius: usize) -> Vec<(String, Vec<String>)> {
+        self.repetitions(ast)
+            .into_iter()
+            .filter(|group| group.highlighted)
+            .map(|group| {
+                let snippets = group.positions
+                                    .iter()
+                                    .map(|&pos| self.context_snippet(ast, pos, radius))
+                                    .collect();
+                (group.stemmed, snippets)
+            })
+            .collect()
+    }
+
+    /// Returns the text of the body of `ast` from `radius` words before to `radius` words
+    /// after `pos`, used by `repetition_contexts`
+    fn context_snippet(&self, ast: &Ast, pos: usize, radius: usize) -> String {
+        let range = ast.body_range();
+        let is_word = |word: &Word| !matches!(*word, Word::Untracked(_));
+        let mut begin = pos;
+        let mut count = 0;
+        while begin > range.start && count < radius {
+            begin -= 1;
+            if is_word(&ast.words[begin]) {
+                count += 1;
+            }
+        }
+        let mut end = pos + 1;
+        let mut count = 0;
+        while end < range.end && count < radius {
+            if is_word(&ast.words[end]) {
+                count += 1;
+            }
+            end += 1;
+        }
+        // Include the punctuation that follows the last word
+        while end < range.end && !is_word(&ast.words[end]) &&
+              !ast.words[end].as_str().contains(char::is_whitespace) {
+            end += 1;
+        }
+        let mut snippet = String::new();
+        for word in &ast.words[begin..end] {
+            let s = word.as_str();
+            if !(self.html && s.starts_with('<')) {
+                snippet.push_str(s);
+            }
+        }
+        snippet.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Returns a CSV table with one row for each highlighted word.
+    ///
+    /// Columns are the stem, the word as it appears in the text, its position (in characters, from
+    /// the beginning of the text), its line number (starting at 1) and its repetition value.
+    /// The first row contains the column names.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.occurrences_to_csv(&ast),
+    ///            "stem,surface,position,line,value\nword,word,5,1,2\nword,words,31,1,2\n");
+    /// ```
+    pub fn occurrences_to_csv(&self, ast: &Ast) -> String {
+        let mut res = String::from("stem,surface,position,line,value\n");
+        let mut pos = 0;
+        let mut line = 1;
+
+        for word in &ast.words {
+            match *word {
+                Word::Untracked(ref s) | Word::Ignored(ref s) => {
+                    pos += s.chars().count();
+                    line += s.matches('\n').count();
+                }
+                Word::Tracked(ref s, ref stemmed, v, ref highlight, _) => {
+                    if highlight.is_some() {
+                        res.push_str(&format!("{},{},{},{},{}\n",
+                                              csv_escape(stemmed),
+                                              csv_escape(s),
+                                              pos,
+                                              line,
+                                              v));
+                    }
+                    pos += s.chars().count();
+                }
+            }
+        }
+        res
+    }
+
+    /// Returns a CSV table with one row for each word that is used several times.
+    ///
+    /// Columns are the stem, its number of occurrences, the positions (in characters, from the
+    /// beginning of the text) of its first and last occurrences and its highest repetition value.
+    /// The first row contains the column names, and rows are ordered by first occurrence (see
+    /// `repetitions`).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let csv = parser.ast_to_csv(&ast);
+    /// let mut lines = csv.lines();
+    /// assert_eq!(lines.next(), Some("stem,count,first_position,last_position,max_value"));
+    /// assert_eq!(lines.next(), Some("word,2,5,31,2"));
+    /// assert_eq!(lines.next(), None);
+    /// ```
+    pub fn ast_to_csv(&self, ast: &Ast) -> String {
+        let mut res = String::from("stem,count,first_position,last_position,max_value\n");
+        // Position (in characters) of each word
+        let mut offsets = Vec::with_capacity(ast.words.len());
+        let mut pos = 0;
+        for word in &ast.words {
+            offsets.push(pos);
+            pos += word.as_str().chars().count();
+        }
+
+        for group in self.repetitions(ast) {
+            res.push_str(&format!("{},{},{},{},{}\n",
+                                  csv_escape(&group.stemmed),
+                                  group.positions.len(),
+                                  offsets[group.positions[0]],
+                                  offsets[group.positions[group.positions.len() - 1]],
+                                  group.value));
+        }
+        res
+    }
+
+    /// Display repetitions in an ispell-compatible manner
+    ///
+    /// This is used if you want to run caribon instead of a text editor, making it pretend to be ispell
+    ///
+    /// Will print as errors words whose repetition value is above threshold
+    ///
+    /// # Arguments
+    ///
+    /// * ast: the ast that must be printed
+    /// * list: only display error (`--list` option)
+    /// * offset: the offset to beginning of the line
+    pub fn ast_to_ispell(&self, ast: &Ast, list: bool, offset: usize) -> String {
+        let mut res = String::new();
+        let words = &ast.words;
+        let mut pos = offset;
+
+        for word in words {
+            match *word {
+                Word::Untracked(ref s) => pos += s.chars().count(),
+                Word::Ignored(ref s) => {
+                    pos += s.chars().count();
+                    if !list {
+                        res.push_str("*\n");
+                    }
+                },
+                Word::Tracked(ref s, _, _, ref highlight, _) => {
+                    if highlight.is_some() {
+                        if !list {
+                            res.push_str(&format!("# {} {}\n", s, pos));
+                        } else {
+                            res.push_str(&format!("{}\n", s));
+                        }
+                    } else {
+                        if !list {
+                            res.push_str("*\n");
+                        }
+                    }
+                    pos += s.chars().count();
+                }
+            }
+        }
+        res
+    }
+
+
+    /// Display the Ast to markdown, emphasizing the repetitions.
+    ///
+    /// This is more limited than HTML or even terminal output, as it completely discards
+    /// colour information that have been passed by `detect_*` methods, but it might be useful
+    /// if e.g. you want to generate some files later with Pandoc (or any other program).
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – An Ast containing repetitions.
+    pub fn ast_to_markdown(&self, ast: &Ast) -> String {
+        let mut res = String::new();
+        let words = &ast.words;
+
+        for word in words {
+            match *word {
+                Word::Untracked(ref s) => res = res + s,
+                Word::Ignored(ref s) => res = res + s,
+                Word::Tracked(ref s, _, _, ref highlight, _) => {
+                    if highlight.is_some() {
+                        res.push_str("**");
+                        res.push_str(s);
+                        res.push_str("**");
+                    } else {
+                        res.push_str(s);
+                    }
+                }
+            }
+        }
+        res
+    }
+
+    /// Display the Ast to plain text, marking the repetitions.
+    ///
+    /// The text is reproduced as is, except that highlighted words are surrounded by the
+    /// markers set by `with_highlight_markers` (by default, "[[" and "]]"). Like
+    /// `ast_to_markdown`, this discards colour information, but the output can easily be
+    /// used with e.g. grep or diff.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("The word, the words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_text(&ast), "The [[word]], the [[words]]");
+    /// ```
+    pub fn ast_to_text(&self, ast: &Ast) -> String {
+        let (ref open, ref close) = self.highlight_markers;
+        let mut res = String::new();
+        for word in &ast.words {
+            match *word {
+                Word::Tracked(ref s, _, _, Some(_), _) => {
+                    res.push_str(open);
+                    res.push_str(s);
+                    res.push_str(close);
+                }
+                _ => res.push_str(word.as_str()),
+            }
+        }
+        res
+    }
+
+    /// Display the Ast to JSON, so it can be used by other programs.
+    ///
+    /// The result is an array containing an object for each word, with its `text` and
+    /// `kind` ("untracked", "ignored" or "tracked"). Tracked words also have their `stemmed`
+    /// form and repetition `value`, and highlighted words the name of their `colour`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("A \"word\", a word").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let json = parser.ast_to_json(&ast);
+    /// assert!(json.starts_with(r#"[{"text":"A","kind":"ignored"},{"text":" \"","kind":"untracked"}"#));
+    /// assert!(json.contains(r#"{"text":"word","kind":"tracked","stemmed":"word","value":2.0,"colour":"green"}"#));
+    /// ```
+    pub fn ast_to_json(&self, ast: &Ast) -> String {
+        let words: Vec<JsonWord> = ast.words
+            .iter()
+            .map(|word| {
+                match *word {
+                    Word::Untracked(ref s) | Word::Ignored(ref s) => {
+                        JsonWord {
+                            text: s,
+                            kind: if let Word::Untracked(_) = *word {
+                                WordKind::Untracked
+                            } else {
+                                WordKind::Ignored
+                            },
+                            stemmed: None,
+                            value: None,
+                            colour: None,
+                        }
+                    }
+                    Word::Tracked(ref s, ref stemmed, v, ref colour, _) => {
+                        JsonWord {
+                            text: s,
+                            kind: WordKind::Tracked,
+                            stemmed: Some(stemmed),
+                            value: Some(v),
+                            colour: colour.as_ref().map(|c| c.as_str()),
+                        }
+                    }
+                }
+            })
+            .collect();
+        // Serializing strings and numbers can't fail
+        serde_json::to_string(&words).unwrap()
+    }
+
+    /// Display the Ast to XML, e.g. to merge the results with a TEI document.
+    ///
+    /// The text is contained in a `<text>` element, and each tracked word in a `<w>` element
+    /// with its stem (`stem`) and repetition value (`rep`) as attributes, as well as its colour
+    /// (`hl`) if it is highlighted. If the input is in HTML, only the body is displayed and HTML
+    /// tags are skipped, so the output only contains the text; everything else is escaped.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false);
+    /// let mut ast = parser.tokenize("Fish & chips <3, fish & chips").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let xml = parser.ast_to_xml(&ast);
+    /// assert_eq!(&xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<text>\
+    ///                   <w stem=\"fish\" rep=\"2\" hl=\"green\">Fish</w> &amp; \
+    ///                   <w stem=\"chip\" rep=\"2\" hl=\"green\">chips</w> &lt;3, \
+    ///                   <w stem=\"fish\" rep=\"2\" hl=\"green\">fish</w> &amp; \
+    ///                   <w stem=\"chip\" rep=\"2\" hl=\"green\">chips</w></text>\n");
+    /// ```
+    pub fn ast_to_xml(&self, ast: &Ast) -> String {
+        let mut res = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<text>");
+        for word in ast.get_body() {
+            match *word {
+                Word::Untracked(ref s) if self.html && s.starts_with('<') => {}
+                Word::Untracked(ref s) | Word::Ignored(ref s) => res.push_str(&xml_escape(s)),
+                Word::Tracked(ref s, ref stemmed, v, ref colour, _) => {
+                    res.push_str(&format!("<w stem=\"{}\" rep=\"{}\"", xml_escape(stemmed), v));
+                    if let Some(ref colour) = *colour {
+                        res.push_str(&format!(" hl=\"{}\"", xml_escape(colour)));
+                    }
+                    res.push_str(&format!(">{}</w>", xml_escape(s)));
+                }
+            }
+        }
+        res.push_str("</text>\n");
+        res
+    }
+
+    /// Display the Ast to RTF, higlighting the repetitions.
+    ///
+    /// This outputs a minimal RTF document, that can be opened by most word processors.
+    /// Highlighted words are underlined, and coloured on a gradient going from green to red
+    /// according to the multiple of the threshold that selected their colour, as in
+    /// `ast_to_terminal_truecolor`. HTML tags are not displayed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(false);
+    /// let mut ast = parser.tokenize("one {two} one two one").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let rtf = parser.ast_to_rtf(&ast);
+    /// assert!(rtf.starts_with("{\\rtf1"));
+    /// assert!(rtf.contains("{\\colortbl;\\red255\\green255\\blue0;\\red0\\green255\\blue0;}"));
+    /// assert!(rtf.contains("{\\ul\\cf1 one} \\{{\\ul\\cf2 two}\\}"));
+    /// assert!(rtf.ends_with("}\n"));
+    /// ```
+    pub fn ast_to_rtf(&self, ast: &Ast) -> String {
+        // Colours of the table, in order of appearance (index 0 is the default colour)
+        let mut colours: Vec<(u8, u8)> = vec![];
+        let mut body = String::new();
+        for word in ast.get_body() {
+            match *word {
+                Word::Untracked(ref s) if self.html && s.starts_with('<') => {}
+                Word::Tracked(ref s, _, _, Some(ref colour), _) => {
+                    let colour = get_gradient_colour(self.gradient_position(colour));
+                    let index = match colours.iter().position(|&c| c == colour) {
+                        Some(i) => i + 1,
+                        None => {
+                            colours.push(colour);
+                            colours.len()
+                        }
+                    };
+                    body.push_str(&format!("{{\\ul\\cf{} {}}}", index, rtf_escape(s)));
+                }
+                _ => body.push_str(&rtf_escape(word.as_str())),
             }
         }
+
+        let mut res = String::from("{\\rtf1\\ansi\\deff0\n{\\fonttbl{\\f0 Times New Roman;}}\n");
+        res.push_str("{\\colortbl;");
+        for &(r, g) in &colours {
+            res.push_str(&format!("\\red{}\\green{}\\blue0;", r, g));
+        }
+        res.push_str("}\n\\f0\\fs24\n");
+        res.push_str(&body);
+        res.push_str("\n}\n");
+        res
     }
 
-    fn tokenize_html<'b>(&self,
-                         c: &'b [char],
-                         ast: &mut Ast,
-                         in_body: &mut bool)
-                         -> TokenizeResult<'b> {
-        let mut res = String::new();
-        let mut chars: &[char] = c;
-        let mut brackets = 1;
-        let mut was_tag_found = false;
 
-        res.push(chars[0]);
-        chars = &chars[1..];
+    /// Display the Ast to PDF, higlighting the repetitions.
+    ///
+    /// This only uses a minimal PDF writer: the text is laid out on A4 pages with a single font,
+    /// and highlighted words are coloured and underlined. HTML tags are not displayed, and
+    /// characters that are not in the Latin-1 charset are replaced by '?'.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let pdf = String::from_utf8(parser.ast_to_pdf(&ast)).unwrap();
+    /// assert!(pdf.starts_with("%PDF-1.4"));
+    /// assert!(pdf.ends_with("%%EOF\n"));
+    /// assert!(pdf.contains("0.000 0.502 0.000 rg BT /F1 11 Tf 79.84 775.00 Td (word) Tj ET"));
+    /// ```
+    pub fn ast_to_pdf(&self, ast: &Ast) -> Vec<u8> {
+        let mut writer = PdfWriter::new();
+        for word in ast.get_body() {
+            let (s, colour) = match *word {
+                Word::Untracked(ref s) if self.html && s.starts_with('<') => continue,
+                Word::Untracked(ref s) | Word::Ignored(ref s) => (s, None),
+                Word::Tracked(ref s, _, _, ref colour, _) => {
+                    (s, colour.as_ref().map(|c| self.pdf_colour(c)))
+                }
+            };
+            // Whitespace is laid out by the writer, so we split the words on it
+            let mut current = String::new();
+            for c in s.chars() {
+                if c.is_whitespace() {
+                    if !current.is_empty() {
+                        writer.write_word(&current, colour);
+                        current.clear();
+                    }
+                    if c == '\n' {
+                        writer.new_line();
+                    } else {
+                        writer.write_space();
+                    }
+                } else {
+                    current.push(c);
+                }
+            }
+            if !current.is_empty() {
+                writer.write_word(&current, colour);
+            }
+        }
+        writer.finish()
+    }
 
-        // Inner function to determine if a char is part of a possible tag name
-        fn is_tag(c: char) -> bool {
-            c == '[' || c == ']' || c == '/' || c.is_alphabetic()
+    /// Display the Ast to HTML, only annotating the repetitions.
+    ///
+    /// Highlighted words are wrapped in `<span class = "caribon-rep">` elements, without any
+    /// style or Javascript, so you can use your own; everything else (including the head of
+    /// the document, if any) is output exactly as in the input. This is meant for HTML input
+    /// that must be inserted in another page, e.g. by a CMS.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – An Ast containing repetitions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+    /// let input = "<p class=\"intro\">Some <em>text</em> &amp; more text.</p>\n";
+    /// let mut ast = parser.tokenize(input).unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.ast_to_html_fragment(&ast);
+    /// assert_eq!(&html, "<p class=\"intro\">Some <em><span class = \"caribon-rep\">text</span></em> \
+    ///                    &amp; more <span class = \"caribon-rep\">text</span>.</p>\n");
+    /// assert_eq!(html.replace("<span class = \"caribon-rep\">", "").replace("</span>", ""),
+    ///            input);
+    /// ```
+    pub fn ast_to_html_fragment(&self, ast: &Ast) -> String {
+        let mut res = String::new();
+        for word in &ast.words {
+            match *word {
+                Word::Tracked(ref s, _, _, Some(_), _) => {
+                    res.push_str("<span class = \"caribon-rep\">");
+                    res.push_str(s);
+                    res.push_str("</span>");
+                }
+                _ => res.push_str(word.as_str()),
+            }
         }
+        res
+    }
 
-        loop {
-            if chars.is_empty() {
-                return Err(Error {
-                    content: format!("Error reading HTML: unclosed tag. Maybe this is not an HTML \
-                                      file?
-Details: the following was not closed: {}",
-                                     res),
+    /// Display the Ast to HTML, higlighting the repetitions.
+    ///
+    /// Use some basic CSS/Js for underlining repetitions and highlighting the
+    /// over occurrences of the word under the mouse.
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – An Ast containing repetitions.
+    /// * `standalone` –  If true, generate a standalone HTML file, else just an HTML fragment
+    ///
+    /// A standalone file always declares its encoding (UTF-8), even if the input had a head
+    /// without a `<meta charset>` tag. If the input has a head, this is done by inserting a
+    /// word in the Ast, after which the positions stored in the Ast (e.g. `sentences()`) are
+    /// updated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap().with_html(true);
+    /// let mut ast = parser.tokenize("<html><head><title>Été</title></head>\
+    ///                                <body>Un été. Un été</body></html>").unwrap();
+    /// let sentence = ast.sentences()[0];
+    /// let html = parser.ast_to_html(&mut ast, true);
+    /// assert!(html.starts_with("<html><head><meta charset = \"UTF-8\">"));
+    /// assert_eq!(ast.sentences(), &[sentence + 1]);
+    /// assert_eq!(ast.words[sentence + 1].as_str(), "Un");
+    ///
+    /// let mut ast = parser.tokenize("Un été").unwrap();
+    /// let html = parser.ast_to_html(&mut ast, true);
+    /// assert!(html.contains("<meta charset = \"UTF-8\">"));
+    /// ```
+    pub fn ast_to_html(&self, ast: &mut Ast, standalone: bool) -> String {
+        let mut res = String::new();
+        let words: &[Word];
+
+        // If standalone, only use words located between <body> and </body>
+        if !standalone {
+            // If standalone, only prints the body part of the AST
+            words = ast.get_body();
+        } else {
+            // There is a head, so we must insert the scripts in the right place
+            if let Some(i) = ast.begin_head {
+                // Declare the encoding, unless the head already does
+                let head_end = ast.begin_body.unwrap_or(ast.words.len());
+                let has_charset = ast.words[i..head_end].iter().any(|w| match *w {
+                    Word::Untracked(ref s) => s.to_lowercase().contains("charset"),
+                    _ => false,
                 });
+                let scripts = if has_charset {
+                    SCRIPTS.to_owned()
+                } else {
+                    format!("<meta charset = \"UTF-8\">\n{}", SCRIPTS)
+                };
+                ast.insert_word(i + 1, Word::Untracked(scripts.into()));
+            } else {
+                // If there is no head, generate the beginning of the document
+                res.push_str("<html><head>\n");
+                res.push_str("<meta charset = \"UTF-8\">\n");
+                res.push_str(SCRIPTS);
+                res.push_str("</head>\n");
+                if ast.begin_body.is_none() || ast.end_body.is_none() {
+                    res.push_str("<body>\n");
+                }
             }
-            let c = chars[0];
-            res.push(c);
-            if !was_tag_found && is_tag(c) {
-                was_tag_found = true;
+            words = &ast.words;
+        }
+        let end_body = ast.end_body;
 
-                let tag: String = chars.iter()
-                                       .take_while(|c: &&char| is_tag(**c))
-                                       .map(|c| c.to_lowercase().collect::<String>())
-                                       .fold(String::new(), |acc, x| acc + &x);
-                match &*tag {
-                    "head" => {
-                        ast.mark_begin_head();
-                        *in_body = false;
-                    }
-                    "body" => {
-                        ast.mark_begin_body();
-                        *in_body = true;
-                    }
-                    "/body" => {
-                        ast.mark_end_body();
-                        *in_body = false;
-                    }
-                    "html" => {
-                        *in_body = false;
-                    }
-                    "[cdata[" => {
-                        // Special loop for CDATA
-                        chars = &chars[1..];
-                        loop {
-                            if chars.len() < 2 {
-                                return Err(Error::new("CDATA field not terminated properly"));
-                            }
-                            res.push(chars[0]);
-                            if chars[0] == ']' && chars[1] == ']' {
-                                res.push(chars[1]);
-                                chars = &chars[2..];
-                                break;
-                            } else {
-                                chars = &chars[1..];
-                            }
-                        }
-                        continue;
-                    }
-                    _ => (),
-                }
+        // Summary panel and words that must have an anchor
+        let (summary, mut anchors) = match self.html_summary {
+            Some(n) if standalone => self.html_summary(words, n),
+            _ => (String::new(), HashSet::new()),
+        };
+
+        self.push_words_html(&mut res, words, &mut anchors, end_body, &summary);
+
+        if standalone && end_body.is_none() {
+            res.push_str(&summary);
+        }
+        if standalone && ast.begin_body.is_none() && ast.end_body.is_none() {
+            // We need to add </body> at the end
+            res.push_str("</body></html>");
+        }
+        res
+    }
+
+    /// Display a range of words of the Ast to HTML, higlighting the repetitions.
+    ///
+    /// The HTML of each word is the same as the one `ast_to_html` gives, so this can be used
+    /// to only update a part of a document (e.g. a paragraph that has been modified).
+    ///
+    /// # Arguments
+    ///
+    /// * `ast` – An Ast containing repetitions.
+    /// * `word_range` – The indexes of the words to display in `ast`.
+    /// * `standalone` –  If true, generate a standalone HTML file, else just an HTML fragment
+    ///
+    /// # Panics
+    ///
+    /// Panics if `word_range` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let html = parser.render_range(&ast, 0..5, false) + &parser.render_range(&ast, 5..15, false);
+    /// assert_eq!(html, parser.ast_to_html(&mut ast, false));
+    /// ```
+    pub fn render_range(&self, ast: &Ast, word_range: Range<usize>, standalone: bool) -> String {
+        let mut res = String::new();
+        if standalone {
+            res.push_str("<html><head>\n");
+            res.push_str("<meta charset = \"UTF-8\">\n");
+            res.push_str(SCRIPTS);
+            res.push_str("</head>\n<body>\n");
+        }
+        self.push_words_html(&mut res, &ast.words[word_range], &mut HashSet::new(), None, "");
+        if standalone {
+            res.push_str("</body></html>");
+        }
+        res
+    }
+
+    /// Display two versions of a document side by side, e.g. to compare drafts.
+    ///
+    /// Each document is displayed in its own column, with its own highlighting (so
+    /// detection must have been run on both). Only the body of each document is used.
+    ///
+    /// # Arguments
+    ///
+    /// * `old` – The document displayed in the left column
+    /// * `new` – The document displayed in the right column
+    /// * `standalone` – If true, generate a whole HTML document
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut old = parser.tokenize("The cat saw another cat.").unwrap();
+    /// parser.detect_local(&mut old, 1.9);
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut new = parser.tokenize("The cat saw a dog.").unwrap();
+    /// parser.detect_local(&mut new, 1.9);
+    ///
+    /// let html = parser.compare_to_html(&old, &new, false);
+    /// let columns: Vec<&str> = html.split("<div class = \"caribon-column\"").collect();
+    /// assert_eq!(columns.len(), 3);
+    /// assert_eq!(columns[1].matches("text-decoration: underline").count(), 2);
+    /// assert_eq!(columns[2].matches("text-decoration: underline").count(), 0);
+    /// ```
+    pub fn compare_to_html(&self, old: &Ast, new: &Ast, standalone: bool) -> String {
+        let mut res = String::new();
+        if standalone {
+            res.push_str("<html><head>\n");
+            res.push_str("<meta charset = \"UTF-8\">\n");
+            res.push_str(SCRIPTS);
+            res.push_str("</head>\n<body>\n");
+        }
+        res.push_str("<div class = \"caribon-comparison\" style = \"display: flex;\">\n");
+        for ast in &[old, new] {
+            res.push_str("<div class = \"caribon-column\" style = \"flex: 1; padding: 1em;\">\n");
+            self.push_words_html(&mut res, ast.get_body(), &mut HashSet::new(), None, "");
+            res.push_str("\n</div>\n");
+        }
+        res.push_str("</div>\n");
+        if standalone {
+            res.push_str("</body></html>");
+        }
+        res
+    }
+
+    /// Appends the HTML of some words to `res`, inserting `summary` before the word at `end_body`
+    fn push_words_html(&self,
+                       res: &mut String,
+                       words: &[Word],
+                       anchors: &mut HashSet<String>,
+                       end_body: Option<usize>,
+                       summary: &str) {
+        // Runs of highlighted words that must be collapsed: beginning -> colour, and ends
+        let mut run_begins = HashMap::new();
+        let mut run_ends = HashSet::new();
+        if self.collapse_highlights {
+            for (begin, end, colour) in highlighted_runs(words) {
+                run_begins.insert(begin, colour);
+                run_ends.insert(end);
             }
-            chars = &chars[1..];
-            if c == '<' {
-                brackets += 1;
+        }
+
+        // Paragraphs: beginning -> density, and ends
+        let mut paragraph_begins = HashMap::new();
+        let mut paragraph_ends = HashSet::new();
+        if self.paragraph_density {
+            for (begin, end, density) in paragraph_densities(words, self.html) {
+                paragraph_begins.insert(begin, density);
+                paragraph_ends.insert(end);
             }
-            if c == '>' {
-                brackets -= 1;
-                if brackets == 0 {
-                    break;
+        }
+
+        for (i, word) in words.iter().enumerate() {
+            if Some(i) == end_body {
+                res.push_str(summary);
+            }
+            if let Some(density) = paragraph_begins.get(&i) {
+                let attribute = format!("data-repetition-density = \"{:.2}\"", density);
+                if self.html {
+                    // Insert the attribute in the <p> tag
+                    let s = word.as_str();
+                    res.push_str(&format!("{} {}{}", &s[..2], attribute, &s[2..]));
+                    continue;
+                } else {
+                    res.push_str(&format!("<div class = \"caribon-paragraph\" {}>", attribute));
                 }
             }
+            if let Some(colour) = run_begins.get(&i) {
+                res.push_str(&format!("<span class = \"caribon-run\" style = \"text-decoration: \
+                                       underline; color: {};\">",
+                                      self.html_colour(colour)));
+            }
+            self.push_word_html(res, word, anchors);
+            if run_ends.contains(&(i + 1)) {
+                res.push_str("</span>");
+            }
+            if paragraph_ends.contains(&(i + 1)) && !self.html {
+                res.push_str("</div>");
+            }
         }
-        Ok((chars, Word::Untracked(res)))
     }
 
-    fn tokenize_whitespace<'b>(&self, c: &'b [char], is_begin: &mut bool) -> TokenizeResult<'b> {
-        let mut res = String::new();
-        let mut chars: &[char] = c;
-
-        loop {
-            if chars.is_empty() {
-                break;
+    /// Appends the HTML of a word to `res`, with an anchor if its stem is in `anchors`
+    fn push_word_html(&self, res: &mut String, word: &Word, anchors: &mut HashSet<String>) {
+        match *word {
+            Word::Untracked(ref s) | Word::Ignored(ref s) => {
+                if !self.html {
+                    // If input is in text, add <br /> for newlines
+                    let new_str = s.replace("\n", "<br/>\n");
+                    res.push_str(&new_str);
+                } else {
+                    res.push_str(s);
+                }
             }
-            let c = chars[0];
-            if ((c == '<' || c == '&') && self.html) || c.is_alphabetic() {
-                break;
+            Word::Tracked(ref s, ref stemmed, v, ref option, fuzzy) => {
+                let class = self.stem_class(stemmed);
+                let fuzzy = fuzzy && option.is_some() && self.mark_fuzzy;
+                let this = format!("<span {}class = \"{}{}\" onmouseover = 'on(\"{}\")' \
+                                    onmouseout = 'off(\"{}\")' {}{}>{}</span>",
+                                   if anchors.remove(stemmed) {
+                                       format!("id = \"caribon-{}\" ", class)
+                                   } else {
+                                       String::new()
+                                   },
+                                   class,
+                                   if fuzzy { " caribon-fuzzy" } else { "" },
+                                   class,
+                                   class,
+                                   if let Some(colour) = option {
+                                       format!("style = \"text-decoration: underline{}; color: \
+                                                {};\"",
+                                               if fuzzy { " dotted" } else { "" },
+                                               self.html_colour(colour))
+                                   } else {
+                                       String::new()
+                                   },
+                                   if option.is_some() && self.html_tooltips {
+                                       format!(" title = \"repetition value: {}\"", v)
+                                   } else {
+                                       String::new()
+                                   },
+                                   s);
+                res.push_str(&this);
             }
-            chars = &chars[1..];
-            res.push(c);
-            if c == '.' {
-                *is_begin = true;
+        }
+    }
+
+    /// Returns the HTML summary panel listing the `n` most repeated words, and the set of
+    /// stems whose first occurrence must have an anchor
+    fn html_summary(&self, words: &[Word], n: usize) -> (String, HashSet<String>) {
+        // stem -> (first occurrence, number of occurrences, highlighted at least once)
+        let mut stats: HashMap<&str, (&str, u32, bool)> = HashMap::new();
+        for word in words {
+            if let Word::Tracked(ref s, ref stemmed, _, ref option, _) = *word {
+                let entry = stats.entry(stemmed).or_insert((s, 0, false));
+                entry.1 += 1;
+                entry.2 = entry.2 || option.is_some();
             }
         }
+        let mut top: Vec<_> = stats.into_iter().filter(|&(_, (_, _, h))| h).collect();
+        top.sort_by(|a, b| (b.1).1.cmp(&(a.1).1).then(a.0.cmp(b.0)));
+        top.truncate(n);
+
+        let mut res = String::new();
+        res.push_str("<div style = \"position: fixed; top: 1em; right: 1em; padding: 0.5em; \
+                      background-color: white; border: 1px solid gray;\">\n");
+        res.push_str("<b>Most repeated words</b>\n<ol>\n");
+        for &(stemmed, (s, count, _)) in &top {
+            res.push_str(&format!("<li><a href = \"#caribon-{}\">{}</a> ({})</li>\n",
+                                  self.stem_class(stemmed),
+                                  s,
+                                  count));
+        }
+        res.push_str("</ol>\n</div>\n");
 
-        Ok((chars, Word::Untracked(res)))
+        (res, top.iter().map(|&(stemmed, _)| stemmed.to_owned()).collect())
     }
 
-    /// Return true if `s` is a proper noun, false else
-    fn is_proper_noun(&self, s: &str, is_begin: bool) -> bool {
-        if self.ignore_proper {
-            if !is_begin {
-                let o = s.chars().next();
-                match o {
-                    None => false,
-                    Some(c) => c.is_uppercase(),
-                }
-            } else {
-                // Technically a proper noun could be at the beginning of a sentence :s
-                false
-            }
+    /// Returns the CSS class used for the words of a stem in HTML output
+    ///
+    /// Characters that could break the HTML attributes or the scripts are replaced by '_'.
+    fn stem_class(&self, stemmed: &str) -> String {
+        let class = if self.lowercase_classes {
+            stemmed.to_lowercase()
         } else {
-            false
+            stemmed.to_owned()
+        };
+        class.chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    }
+
+    /// Returns the terminal escape code for a colour, looking first at user definitions
+    fn shell_colour<'a>(&'a self, colour: &'a str) -> Option<&'a str> {
+        match self.colour_definitions.get(colour) {
+            Some((shell, _)) => Some(shell),
+            None => get_shell_colour(colour),
         }
     }
 
-    fn tokenize_word<'b>(&mut self,
-                         c: &'b [char],
-                         is_begin: &mut bool,
-                         in_body: bool)
-                         -> TokenizeResult<'b> {
-        let mut res = String::new();
-        let mut chars: &[char] = c;
+    /// Returns the CSS colour for a colour name, looking first at user definitions
+    fn html_colour<'a>(&'a self, colour: &'a str) -> &'a str {
+        match self.colour_definitions.get(colour) {
+            Some((_, html)) => html,
+            None => colour,
+        }
+    }
 
-        loop {
-            if chars.is_empty() {
-                break;
+    /// Returns the position (between 0 and 1) of a colour on the gradient used by
+    /// `ast_to_terminal_truecolor` and `ast_to_rtf`, according to its multiple of the threshold
+    /// in the colour scale. Colours that are not in the scale are at the beginning.
+    fn gradient_position(&self, colour: &str) -> f32 {
+        let first = self.colour_scale.first().map_or(0.0, |&(m, _)| m);
+        let last = self.colour_scale.last().map_or(0.0, |&(m, _)| m);
+        match self.colour_scale.iter().find(|(_, c)| c == colour) {
+            Some(&(m, _)) if last > first => (m - first) / (last - first),
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the RGB components (between 0 and 1) of a colour, for PDF output
+    fn pdf_colour(&self, colour: &str) -> (f32, f32, f32) {
+        let colour = self.html_colour(colour);
+        let hex = |s: &str| u8::from_str_radix(s, 16).unwrap_or(0) as f32 / 255.0;
+        if colour.starts_with('#') && colour.len() == 7 {
+            return (hex(&colour[1..3]), hex(&colour[3..5]), hex(&colour[5..7]));
+        }
+        match colour {
+            "red" => (1.0, 0.0, 0.0),
+            "green" => (0.0, 128.0 / 255.0, 0.0),
+            "cyan" => (0.0, 1.0, 1.0),
+            "brown" => (165.0 / 255.0, 42.0 / 255.0, 42.0 / 255.0),
+            "blue" => (0.0, 0.0, 1.0),
+            "purple" => (128.0 / 255.0, 0.0, 128.0 / 255.0),
+            "orange" => (1.0, 165.0 / 255.0, 0.0),
+            _ => (0.0, 0.0, 0.0),
+        }
+    }
+
+    /// Converts a (lowercase) word to the spelling set by `with_spelling_normalization`
+    fn normalize_spelling(&self, s: &str) -> String {
+        if let Some(spelling) = self.spelling {
+            for &(british, american) in SPELLING_VARIANTS {
+                let (from, to) = match spelling {
+                    Spelling::British => (american, british),
+                    Spelling::American => (british, american),
+                };
+                if let Some(rest) = s.strip_prefix(from) {
+                    return format!("{}{}", to, rest);
+                }
             }
-            let c = chars[0];
-            if !c.is_alphabetic() {
-                break;
+        }
+        s.to_owned()
+    }
+
+    /// Stems a string
+    ///
+    /// Either warps call to `stemmer.stem`, or, if `stemmer == None` or stemming is disabled,
+    /// just returns the string.
+    /// If the stemmer returns an empty string, the string is returned unchanged, so unrelated
+    /// words are not counted as repetitions of each other.
+    fn stem(&mut self, s: &str) -> String {
+        if !self.stemming {
+            return s.to_owned();
+        }
+        let stemmed = match self.stemmer {
+            Some(ref mut stemmer) => {
+                let stemmed = stemmer.stem(s);
+                if stemmed.is_empty() { s.to_owned() } else { stemmed }
             }
-            res.push(c);
-            chars = &chars[1..];
+            None => s.to_owned(),
+        };
+        if self.aggressive_stemming &&
+           lang_code(&self.lang).is_some_and(|code| AGGRESSIVE_LANGUAGES.contains(&code)) {
+            stemmed.chars().take(AGGRESSIVE_STEM_LENGTH).collect()
+        } else {
+            stemmed
         }
+    }
 
-        let lower_s: Vec<String> = res.chars()
-                                      .map(|c| c.to_lowercase().collect::<String>())
-                                      .collect();
-        let lower_s = lower_s.concat();
-        let word = if !in_body {
-            // We are not in body, so words are all untracked
-            Word::Untracked(res)
-        } else if self.ignored.contains(&lower_s) || self.is_proper_noun(&res, *is_begin) {
-            Word::Ignored(res)
+    /// Returns the key used to count repetitions of a (lowercase) word: its stem, after
+    /// spelling normalization, or the key of its synonym group if it belongs to one
+    fn tracking_key(&mut self, lower_s: &str) -> String {
+        let normalized = self.normalize_spelling(lower_s);
+        let stemmed = self.stem(&normalized);
+        match self.synonym_groups.get(&stemmed) {
+            Some(key) => key.clone(),
+            None => stemmed,
+        }
+    }
+
+
+    /// Search a string in the keys of a hashmap (grouped by length) with fuzzy string matching
+    /// Returns the matching string, or `pattern` if there is none
+    fn fuzzy_get(&self, index: &LengthIndex, pattern: &str) -> String {
+        if let Some(d_max) = self.fuzzy {
+            let length = pattern.chars().count();
+            if length < 2 {
+                // Pattern is too short to do fuzzy matching
+                pattern.to_owned()
+            } else {
+                // If hashmap contains the exact pattern, no need to fuzzy search
+                if index.get(&length).is_some_and(|keys| keys.contains(pattern)) {
+                    pattern.to_owned()
+                } else {
+                    let mut min_distance = length as i32;
+                    let mut key = pattern;
+                    // Only strings whose lengths allow a matching distance (and that are not
+                    // too small) are compared
+                    let d = d_max * length as f32;
+                    let min_length = ((length as f32 - d).ceil() as usize).max(2);
+                    let max_length = (length as f32 + d).floor() as usize;
+                    'search: for keys in index.range(min_length..max_length + 1).map(|(_, v)| v) {
+                        for s in keys {
+                            let dist = edit_distance(s, pattern) as i32;
+                            if dist < min_distance {
+                                min_distance = dist;
+                                key = s;
+                            }
+                            if min_distance == 1 {
+                                break 'search; // best result since perfect match has been ruled out
+                            }
+                        }
+                    }
+                    let mut limit = (d_max * length as f32) as i32;
+                    if let Some(cap) = self.fuzzy_max_distance {
+                        limit = limit.min(cap as i32);
+                    }
+                    if min_distance <= limit {
+                        key.to_owned()
+                    } else {
+                        pattern.to_owned()
+                    }
+                }
+            }
         } else {
-            Word::Tracked(res, self.stem(&lower_s), 0.0, None)
-        };
+            pattern.to_owned()
+        }
+    }
+}
 
-        *is_begin = false;
-        Ok((chars, word))
+/// Returns the ISO 639-1 code of a language, given either its name or its code
+fn lang_code(lang: &str) -> Option<&'static str> {
+    match lang {
+        "english" | "en" => Some("en"),
+        "french" | "fr" => Some("fr"),
+        "danish" | "da" => Some("da"),
+        "norwegian" | "no" | "nb" => Some("no"),
+        "swedish" | "sv" => Some("sv"),
+        "catalan" | "ca" => Some("ca"),
+        "breton" | "br" => Some("br"),
+        "finnish" | "fi" => Some("fi"),
+        "hungarian" | "hu" => Some("hu"),
+        _ => None,
     }
+}
 
+/// Returns the time required to read `count` words at `wpm` words per minute
+fn words_to_duration(count: u32, wpm: u32) -> Duration {
+    assert!(wpm > 0, "reading speed must be greater than zero");
+    Duration::from_millis(count as u64 * 60000 / wpm as u64)
+}
 
-    /// Tokenize a string into a list of words. 
-    ///
-    /// This is the step that converts a string to some inner representation.
-    ///
-    /// # Arguments
-    ///
-    /// * `s` – The string to tokenize.
-    pub fn tokenize(&mut self, s: &str) -> Result<Ast> {
-        let v_chars: Vec<char> = s.chars().collect();
-        let mut chars: &[char] = &v_chars;
-        let mut ast = Ast::new();
-        let mut is_sentence_beginning = true;
-        let mut in_body = true;
+/// Returns whether a string is a closing HTML tag and the (lowercase) name of the tag,
+/// or `None` if it is not a tag
+fn html_tag_name(s: &str) -> Option<(bool, String)> {
+    if !s.starts_with('<') {
+        return None;
+    }
+    let closing = s[1..].starts_with('/');
+    let name: String = s.chars()
+        .skip(if closing { 2 } else { 1 })
+        .take_while(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect();
+    if name.is_empty() {
+        None
+    } else {
+        Some((closing, name))
+    }
+}
 
+/// Returns true if a character is a soft hyphen or a zero-width character
+fn is_invisible(c: char) -> bool {
+    matches!(c, '\u{00AD}' | '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
 
-        while !chars.is_empty() {
-            let c = chars[0];
-            let (cs, word) = if c.is_alphabetic() {
-                try!(self.tokenize_word(chars, &mut is_sentence_beginning, in_body))
-            } else if self.html && c == '<' {
-                is_sentence_beginning = false;
-                try!(self.tokenize_html(chars, &mut ast, &mut in_body))
-            } else if self.html && c == '&' {
-                try!(self.tokenize_escape(chars))
-            } else {
-                try!(self.tokenize_whitespace(chars, &mut is_sentence_beginning))
-            };
-            chars = cs;
-            ast.words.push(word);
+/// Returns the ranges (in characters) of the lines that are markdown headings
+fn heading_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let lines = line_ranges(chars);
+    let line_str = |&(b, e): &(usize, usize)| chars[b..e].iter().cloned().collect::<String>();
+
+    // Returns true if a line is only made of '=' or '-' (setext underline)
+    fn is_underline(s: &str) -> bool {
+        let s = s.trim();
+        !s.is_empty() && (s.chars().all(|c| c == '=') || s.chars().all(|c| c == '-'))
+    }
+
+    let mut res = vec![];
+    for i in 0..lines.len() {
+        let line = line_str(&lines[i]);
+        let underlined = !line.trim().is_empty() && i + 1 < lines.len() &&
+                         is_underline(&line_str(&lines[i + 1]));
+        if line.trim_left().starts_with('#') || underlined {
+            res.push(lines[i]);
         }
-        Ok(ast)
     }
+    res
+}
 
+/// Returns true if `pos` is in one of the ranges, which must be sorted.
+///
+/// `cursor` is the index of the first range that might contain `pos`: it must start at 0 and
+/// be given again for each position, which must only increase.
+fn in_ranges(ranges: &[(usize, usize)], cursor: &mut usize, pos: usize) -> bool {
+    while *cursor < ranges.len() && ranges[*cursor].1 <= pos {
+        *cursor += 1;
+    }
+    *cursor < ranges.len() && ranges[*cursor].0 <= pos
+}
 
-    /// Detect the local number of repetitions.
-    ///
-    /// For each word, the repetition value is set to the total number of occurences of this word
-    /// since there has been hat least `self.max_distance` between two occurences.
-    ///
-    /// It is the default algorithm, and probably the one you want to use.
-    ///
-    /// # Arguments
-    ///
-    /// `ast` – A mutable reference to an internal data structure returned by `tokenize`
-    /// `threshold` – The threshold to consider a repetition (e.g. 1.9)
-    ///
-    /// # Example
-    ///
-    /// ```rust
-    /// let mut parser = caribon::Parser::new("english").unwrap();
-    /// let mut ast = parser.tokenize("Testing whether this repetition detector works or does not work").unwrap();
-    /// parser.detect_local(&mut ast, 1.9);
-    /// let result = parser.ast_to_markdown(&ast); // not the most useful output format, but the easiest to debug
-    /// assert_eq!(&result, "Testing whether this repetition detector **works** or does not **work**");
-    /// ```
-    pub fn detect_local(&mut self, ast: &mut Ast, threshold: f32) {
-        let mut vec = ast.get_body_mut();
+/// Lowercases the first letter of a word, unless the word is all in capitals
+fn lowercase_initial(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) if c.is_uppercase() && !chars.clone().any(|c| c.is_uppercase()) => {
+            c.to_lowercase().chain(chars).collect()
+        }
+        _ => s.to_owned(),
+    }
+}
 
-        // must only be called if offset = 0
-        fn try_remove(pos: u32,
-                      h: &mut HashMap<String, (u32, Vec<usize>)>,
-                      vec: &[Word],
-                      pos_to_i: &[usize],
-                      max_distance: u32) {
-            if pos > max_distance + 1 {
-                let pos_limit = pos - max_distance;
-                let i = pos_to_i[pos_limit as usize];
-                let stemmed = match vec[i] {
-                    Word::Untracked(_) => panic!("Should not happen"),
-                    Word::Ignored(_) => return,
-                    Word::Tracked(_, ref stemmed, _, _) => stemmed,
-                };
-                if let Some(&(old_pos, _)) = h.get(stemmed) {
-                    if old_pos == pos_limit + 1 {
-                        h.remove(stemmed);
-                    }
+/// Returns the length of a hyphen followed by a newline, some indentation and a lowercase
+/// letter (excluded) at the beginning of `chars`, if there is one
+fn line_break_hyphen_len(chars: &[char]) -> Option<usize> {
+    let mut i = 1;
+    if chars.get(i) == Some(&'\r') {
+        i += 1;
+    }
+    if chars.get(i) != Some(&'\n') {
+        return None;
+    }
+    i += 1;
+    while chars.get(i).is_some_and(|&c| c == ' ' || c == '\t') {
+        i += 1;
+    }
+    if chars.get(i).is_some_and(|c| c.is_lowercase()) {
+        Some(i)
+    } else {
+        None
+    }
+}
+
+/// Returns the paragraphs of a text, as (beginning, end, density of repetitions), `end`
+/// being excluded
+///
+/// In HTML, paragraphs are `<p>` elements, and begin with the opening tag; in text, they are
+/// separated by blank lines.
+fn paragraph_densities(words: &[Word], html: bool) -> Vec<(usize, usize, f32)> {
+    let mut res = vec![];
+    // Beginning, number of words and number of highlighted words of the current paragraph
+    let mut current: Option<(usize, u32, u32)> = if html { None } else { Some((0, 0, 0)) };
+    for (i, word) in words.iter().enumerate() {
+        let (is_begin, is_end) = match *word {
+            Word::Untracked(ref s) if html => {
+                match html_tag_name(s) {
+                    Some((closing, ref name)) if name == "p" => (!closing, closing),
+                    _ => (false, false),
                 }
             }
+            Word::Untracked(ref s) => {
+                // A blank line must be between two newlines
+                let lines: Vec<&str> = s.split('\n').collect();
+                (false, lines.len() > 2 && lines[1..lines.len() - 1].iter().any(|l| l.trim().is_empty()))
+            }
+            _ => (false, false),
+        };
+        if is_begin {
+            if let Some((begin, n, highlighted)) = current.take() {
+                res.push((begin, i, density(n, highlighted)));
+            }
+            current = Some((i, 0, 0));
         }
-        for i in 0..vec.len() {
-            let elem = match vec[i] {
-                Word::Untracked(_) => None,
-                Word::Ignored(_) => {
-                    self.pos += 1;
-                    self.pos_to_i.push(i + self.offset);
-                    None
-                }
-                Word::Tracked(_, ref stemmed, _, _) => {
-                    self.pos += 1;
-                    self.pos_to_i.push(i + self.offset);
-                    let s = self.fuzzy_get(&self.hash, stemmed);
-                    Some((self.hash.remove(&s), s))
+        match *word {
+            Word::Untracked(_) => (),
+            Word::Ignored(_) => {
+                if let Some((_, ref mut n, _)) = current {
+                    *n += 1;
                 }
-            };
-            // Try to remove elements on a map
-            if self.fuzzy.is_some() && self.offset == 0 {
-                try_remove(self.pos, &mut self.hash, vec, &self.pos_to_i, self.max_distance);
             }
-            if let Some((e, stemmed)) = elem {
-                // Update old stemmed to the fuzzy matched one
-                vec[i].set_stemmed(stemmed.clone());
-                let (p_pos, mut subvec) = match e {
-                    None => (0, vec![]),
-                    Some(y) => y,
-                };
-                if p_pos != 0 && self.pos - p_pos < self.max_distance {
-                    subvec.push(i + self.offset);
-                    let v = subvec.len() as f32;
-                    for x in &subvec {
-                        if *x >= self.offset {
-                            vec[*x - self.offset].set_count(v);
-                        } 
+            Word::Tracked(_, _, _, ref colour, _) => {
+                if let Some((_, ref mut n, ref mut highlighted)) = current {
+                    *n += 1;
+                    if colour.is_some() {
+                        *highlighted += 1;
                     }
-                    self.hash.insert(stemmed, (self.pos, subvec));
-                } else {
-                    subvec = vec![i + self.offset];
-                    self.hash.insert(stemmed, (self.pos, subvec));
                 }
             }
         }
-        self.offset += vec.len();
-        
-        self.highlight(vec, threshold, value_to_colour)
+        if is_end {
+            if let Some((begin, n, highlighted)) = current.take() {
+                res.push((begin, i + 1, density(n, highlighted)));
+            }
+            if !html {
+                current = Some((i + 1, 0, 0));
+            }
+        }
     }
-
-    /// Returns stats about the words
-    ///
-    /// # Arguments
-    ///
-    /// `words` – A reference to a list of words
-    ///
-    /// # Returns
-    ///
-    /// This method retuns a tuple:
-    /// * the first element is a hashmap between stemmed strings and the number of occurences of this word
-    /// * the second oelement is the total number of (valid) words in the list (non counting whitespace, HTML tags...)
-    pub fn words_stats(&self, ast: &Ast) -> (HashMap<String, f32>, u32) {
-        let mut h: HashMap<String, f32> = HashMap::new();
-        let mut count: u32 = 0;
-        let words: &[Word] = ast.get_body();
-
-        // we fill the map and count
-        for word in words {
-            match *word {
-                Word::Untracked(_) => {}
-                Word::Ignored(_) => {
-                    count += 1;
-                }
-                Word::Tracked(_, ref stemmed, _, _) => {
-                    count += 1;
-                    let x = match h.get(stemmed) {
-                        None => 0.0,
-                        Some(y) => *y,
-                    } + 1.0;
-                    h.insert(stemmed.clone(), x);
-                }
-            };
+    if let Some((begin, n, highlighted)) = current {
+        if begin < words.len() {
+            res.push((begin, words.len(), density(n, highlighted)));
         }
-
-        (h, count)
     }
+    res
+}
 
-    /// Detect the global number of repetitions.
-    ///
-    /// For each word, repetition value is set to the total number of occurences of this word in whole text,
-    /// divided by total number of words in the text
-    ///
-    /// # Arguments
-    ///
-    /// * `vec` – A vector of `Word`.
-    /// * `threshold` – A threshold to highlight repetitions (e.g. 0.01)
-    pub fn detect_global(&self, ast: &mut Ast, threshold: f32) {
-        let (h, count) = self.words_stats(&ast);
-        let mut vec = ast.get_body_mut();
-
+/// Returns the proportion of highlighted words
+fn density(n: u32, highlighted: u32) -> f32 {
+    if n == 0 { 0.0 } else { highlighted as f32 / n as f32 }
+}
 
-        // If there are not enough words for the threshold, do nothing instead of
-        // underlining all words
-        if 1.0 / threshold >= (count as f32) {
-            return;
-        }
+/// Returns the ranges (in characters) of the lines that are header rows of tables
+fn table_header_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let lines = line_ranges(chars);
+    let line_str = |&(b, e): &(usize, usize)| chars[b..e].iter().cloned().collect::<String>();
 
-        // We set each word value to the relative number of occurences
-        for i in 0..vec.len() {
-            let tmp = if let Word::Tracked(_, ref stemmed, _, _) = vec[i] {
-                let x = h.get(stemmed).expect("HashMap was not filled correctly");
-                Some(*x)
-            } else {
-                None
-            };
-            if let Some(x) = tmp {
-                vec[i].set_count(x / (count as f32));
+    // Returns true if a line separates the rows of a table: it must contain cell separators,
+    // or several groups of dashes (fixed-width tables)
+    fn is_separator(s: &str) -> bool {
+        let s = s.trim();
+        s.contains(['-', '=']) &&
+        s.chars().all(|c| "-=:|+".contains(c) || c.is_whitespace()) &&
+        (s.contains(['|', '+']) || s.split_whitespace().count() > 1)
+    }
 
-            }
+    let mut res = vec![];
+    for i in 0..lines.len() {
+        let line = line_str(&lines[i]);
+        if !line.trim().is_empty() && !is_separator(&line) && i + 1 < lines.len() &&
+           is_separator(&line_str(&lines[i + 1])) {
+            res.push(lines[i]);
         }
-        self.highlight(vec, threshold, |_, _| "blue")
     }
+    res
+}
 
-    /// Highlight words whose value is superior to the threshold
-    ///
-    /// # Arguments
-    ///
-    /// * `words` – A vector containing repetitions.
-    /// * `threshold` – The threshold above which words must be highlighted.
-    /// * `f` – A closure that returns the colour given the value and threshold
-    ///
-    /// # Returns
-    ///
-    /// A vector of highlight
-    fn highlight<F>(&self, words: &mut [Word], threshold: f32, f: F)
-        where F: Fn(f32, f32) -> &'static str
-    {
-        let mut res = words;
-        for i in 0..res.len() {
-            let word: &mut Word = &mut res[i];
-            if let Word::Tracked(_, _, ref mut v, ref mut option) = *word {
-                if option.is_none() && *v >= threshold {
-                    // No colour is attributed, so see if we attribute one
-                    *option = Some(f(*v, threshold));
-                }
-                *v = 0.0; //resets value to zero so it doesn't cause problem if another algorithm is used after
-            }
+/// Returns the ranges (in chars) of all lines, without the trailing newline
+fn line_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut lines = vec![];
+    let mut begin = 0;
+    for (i, c) in chars.iter().enumerate() {
+        if *c == '\n' {
+            lines.push((begin, i));
+            begin = i + 1;
         }
     }
+    lines.push((begin, chars.len()));
+    lines
+}
 
-    /// Display the words to terminal, higlighting the repetitions.
-    ///
-    /// Use terminal colour codes to highlight the repetitions
-    ///
-    /// # Arguments
-    ///
-    /// * `ast` – A reference to `Ast`, returned by `tokenize` and modified by `detect_*`
-    pub fn ast_to_terminal(&self, ast: &Ast) -> String {
-        let mut res = String::new();
-        let words = &ast.words;
+/// Returns the ranges (in chars) of the lines of a subtitle file (SRT or WebVTT) that are
+/// not dialogue: cue numbers or identifiers, timing lines and the WebVTT header
+fn subtitle_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let lines = line_ranges(chars);
+    let line_str = |&(b, e): &(usize, usize)| chars[b..e].iter().cloned().collect::<String>();
+    let is_timing = |line: &str| line.contains("-->");
 
-        for word in words {
-            match *word {
-                Word::Untracked(ref s) => res = res + s,
-                Word::Ignored(ref s) => res = res + s,
-                Word::Tracked(ref s, _, _, option) => {
-                    if let Some(colour) = option {
-                        match get_shell_colour(colour) {
-                            None => res = res + s,
-                            Some(shell_colour) => res = res + shell_colour + s + SHELL_COLOUR_OFF,
-                        }
-                    } else {
-                        res.push_str(s);
-                    }
+    let mut res = vec![];
+    for i in 0..lines.len() {
+        let line = line_str(&lines[i]);
+        let trimmed = line.trim();
+        if is_timing(&line) || trimmed.starts_with("WEBVTT") ||
+           (!trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_digit())) ||
+           (!trimmed.is_empty() && i + 1 < lines.len() && is_timing(&line_str(&lines[i + 1]))) {
+            res.push(lines[i]);
+        }
+    }
+    res
+}
+
+/// Returns the ranges (in chars) of a Markdown text that are not prose: fenced code blocks,
+/// inline code spans and the URLs of links
+fn markdown_code_ranges(chars: &[char]) -> Vec<(usize, usize)> {
+    let mut res = vec![];
+    // Fence and beginning of the current code block
+    let mut fence: Option<(String, usize)> = None;
+    // Lines that are outside code blocks
+    let mut prose = vec![];
+    for (b, e) in line_ranges(chars) {
+        let line: String = chars[b..e].iter().cloned().collect();
+        let trimmed = line.trim_left();
+        let is_fence = |f: &str| trimmed.starts_with(f);
+        match fence.take() {
+            Some((f, begin)) => {
+                if is_fence(&f) {
+                    res.push((begin, e));
+                } else {
+                    fence = Some((f, begin));
+                }
+            }
+            None => {
+                if is_fence("```") {
+                    fence = Some(("```".to_owned(), b));
+                } else if is_fence("~~~") {
+                    fence = Some(("~~~".to_owned(), b));
+                } else {
+                    prose.push((b, e));
                 }
             }
         }
-        res
+    }
+    // Unclosed code block
+    if let Some((_, begin)) = fence {
+        res.push((begin, chars.len()));
     }
 
-    /// Returns a list of repetitions found in the AST.
-    pub fn ast_to_repetitions(&self, ast: &Ast) -> Vec<Repetition> {
-        let mut res = vec![];
-        let words = &ast.words;
-        let mut pos = 0;
-
-        for word in words {
-            match *word {
-                Word::Untracked(ref s) => pos += s.chars().count(),
-                Word::Ignored(ref s) => pos += s.chars().count(),
-                Word::Tracked(ref s, _, v, highlight) => {
-                    let len = s.chars().count();
-                    if let Some(colour) = highlight {
-                        res.push(Repetition {
-                            offset: pos,
-                            length: len,
-                            value: v,
-                            colour: colour
-                        });
+    for (b, e) in prose {
+        let mut i = b;
+        while i < e {
+            if chars[i] == '`' {
+                // A code span ends with the same number of backticks
+                let n = chars[i..e].iter().take_while(|&&c| c == '`').count();
+                let mut j = i + n;
+                let mut end = None;
+                while j < e {
+                    let m = chars[j..e].iter().take_while(|&&c| c == '`').count();
+                    if m == n {
+                        end = Some(j + m);
+                        break;
                     }
-                    pos += len;
+                    j += ::std::cmp::max(m, 1);
                 }
+                match end {
+                    Some(end) => {
+                        res.push((i, end));
+                        i = end;
+                    }
+                    None => i += n,
+                }
+            } else if chars[i] == ']' && i + 1 < e && chars[i + 1] == '(' {
+                let end = chars[i..e].iter().position(|&c| c == ')').map_or(e, |p| i + p + 1);
+                res.push((i, end));
+                i = end;
+            } else {
+                i += 1;
             }
         }
-        
-        res
     }
+    res
+}
 
-    /// Display repetitions in an ispell-compatible manner
-    ///
-    /// This is used if you want to run caribon instead of a text editor, making it pretend to be ispell
-    ///
-    /// Will print as errors words whose repetition value is above threshold
-    ///
-    /// # Arguments
-    ///
-    /// * ast: the ast that must be printed
-    /// * list: only display error (`--list` option)
-    /// * offset: the offset to beginning of the line
-    pub fn ast_to_ispell(&self, ast: &Ast, list: bool, offset: usize) -> String {
-        let mut res = String::new();
-        let words = &ast.words;
-        let mut pos = offset;
-
-        for word in words {
-            match *word {
-                Word::Untracked(ref s) => pos += s.chars().count(),
-                Word::Ignored(ref s) => {
-                    pos += s.chars().count();
-                    if !list {
-                        res.push_str("*\n");
-                    }
-                },
-                Word::Tracked(ref s, _, _, highlight) => {
-                    if highlight.is_some() {
-                        if !list {
-                            res.push_str(&format!("# {} {}\n", s, pos));
-                        } else {
-                            res.push_str(&format!("{}\n", s));
-                        }
-                    } else {
-                        if !list {
-                            res.push_str("*\n");
-                        }
+/// Returns the runs of (at least two) highlighted words separated only by whitespace, as
+/// (beginning, end, colour of the first word), `end` being excluded
+fn highlighted_runs<'a>(words: &'a [Word]) -> Vec<(usize, usize, &'a str)> {
+    let mut res = vec![];
+    // Beginning, end and colour of the current run, and number of highlighted words in it
+    let mut current: Option<(usize, usize, &str, u32)> = None;
+    for (i, word) in words.iter().enumerate() {
+        match *word {
+            Word::Tracked(_, _, _, Some(ref colour), _) => {
+                current = match current {
+                    Some((begin, _, c, n)) => Some((begin, i + 1, c, n + 1)),
+                    None => Some((i, i + 1, colour, 1)),
+                };
+            }
+            Word::Untracked(ref s) if s.chars().all(|c| c.is_whitespace()) => (),
+            _ => {
+                if let Some((begin, end, c, n)) = current.take() {
+                    if n > 1 {
+                        res.push((begin, end, c));
                     }
-                    pos += s.chars().count();
                 }
             }
         }
-        res
     }
+    if let Some((begin, end, c, n)) = current {
+        if n > 1 {
+            res.push((begin, end, c));
+        }
+    }
+    res
+}
 
+/// Strings grouped by length (in characters), used for fuzzy string matching
+type LengthIndex = BTreeMap<usize, BTreeSet<String>>;
 
-    /// Display the Ast to markdown, emphasizing the repetitions.
-    ///
-    /// This is more limited than HTML or even terminal output, as it completely discards
-    /// colour information that have been passed by `detect_*` methods, but it might be useful
-    /// if e.g. you want to generate some files later with Pandoc (or any other program).
-    ///
-    /// # Arguments
-    ///
-    /// * `ast` – An Ast containing repetitions.
-    pub fn ast_to_markdown(&self, ast: &Ast) -> String {
-        let mut res = String::new();
-        let words = &ast.words;
+/// Adds a string to a `LengthIndex`
+fn index_insert(index: &mut LengthIndex, s: &str) {
+    index.entry(s.chars().count()).or_default().insert(s.to_owned());
+}
 
-        for word in words {
-            match *word {
-                Word::Untracked(ref s) => res = res + s,
-                Word::Ignored(ref s) => res = res + s,
-                Word::Tracked(ref s, _, _, highlight) => {
-                    if let Some(_) = highlight {
-                        res.push_str("**");
-                        res.push_str(s);
-                        res.push_str("**");
-                    } else {
-                        res.push_str(s);
-                    }
+/// Removes a string from a `LengthIndex`
+fn index_remove(index: &mut LengthIndex, s: &str) {
+    let length = s.chars().count();
+    let empty = match index.get_mut(&length) {
+        Some(keys) => {
+            keys.remove(s);
+            keys.is_empty()
+        }
+        None => false,
+    };
+    if empty {
+        index.remove(&length);
+    }
+}
+
+/// Adds the position of an error in the input, and the text around it
+///
+/// `pos` is the position of the error in `chars`, whose first character is at position
+/// `offset` in the input.
+fn located_error(e: Error, chars: &[char], pos: usize, offset: usize) -> Error {
+    let begin = pos.saturating_sub(ERROR_CONTEXT);
+    let end = ::std::cmp::min(chars.len(), pos + ERROR_CONTEXT);
+    let snippet: String = chars[begin..end].iter().cloned().collect();
+    Error::with_position(&format!("{} Near: {:?}", e.content, snippet), pos + offset)
+}
+
+/// Returns, for each tracked word, the distance (in words) to the nearest other word with the
+/// same stem
+fn nearest_distances(words: &[Word]) -> Vec<Option<u32>> {
+    let mut res = vec![None; words.len()];
+    // stem -> (index, position in words) of its last occurrence
+    let mut last: HashMap<&str, (usize, u32)> = HashMap::new();
+    let mut pos = 0;
+    for (i, word) in words.iter().enumerate() {
+        match *word {
+            Word::Untracked(_) => continue,
+            Word::Ignored(_) => (),
+            Word::Tracked(_, ref stemmed, _, _, _) => {
+                if let Some(&(j, p)) = last.get(stemmed.as_str()) {
+                    let d = pos - p;
+                    res[j] = Some(res[j].map_or(d, |x: u32| x.min(d)));
+                    res[i] = Some(d);
                 }
+                last.insert(stemmed, (i, pos));
             }
         }
-        res
+        pos += 1;
     }
+    res
+}
 
+/// Marks the words whose stem is among the `percentile` percent most frequent ones as ignored
+fn ignore_most_frequent(ast: &mut Ast, percentile: f32) {
+    let mut counts: HashMap<String, u32> = HashMap::new();
+    for word in &ast.words {
+        if let Word::Tracked(_, ref stemmed, _, _, _) = *word {
+            *counts.entry(stemmed.clone()).or_insert(0) += 1;
+        }
+    }
+    let n = (counts.len() as f32 * percentile / 100.0).ceil() as usize;
+    let mut counts: Vec<(String, u32)> = counts.into_iter().filter(|&(_, c)| c > 1).collect();
+    // Most frequent first, and alphabetical order for stems with the same count
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let frequent: HashSet<String> = counts.into_iter().take(n).map(|(s, _)| s).collect();
+    for i in 0..ast.words.len() {
+        let is_frequent = match ast.words[i] {
+            Word::Tracked(_, ref stemmed, _, _, _) => frequent.contains(stemmed),
+            _ => false,
+        };
+        if is_frequent {
+            ast.set_ignored(i);
+        }
+    }
+}
 
-    /// Display the Ast to HTML, higlighting the repetitions.
-    ///
-    /// Use some basic CSS/Js for underlining repetitions and highlighting the
-    /// over occurrences of the word under the mouse.
-    ///
-    /// # Arguments
-    ///
-    /// * `ast` – An Ast containing repetitions.
-    /// * `standalone` –  If true, generate a standalone HTML file, else just an HTML fragment
-    pub fn ast_to_html(&self, ast: &mut Ast, standalone: bool) -> String {
-        let mut res = String::new();
-        let words: &[Word];
+/// Marks words that are repeated inside an enumeration (at least three words separated by
+/// commas or semicolons) as ignored, except for their first occurrence
+fn ignore_enumerations(ast: &mut Ast) {
+    fn is_delimiter(word: &Word) -> bool {
+        match *word {
+            Word::Untracked(ref s) => {
+                let s = s.trim();
+                s == "," || s == ";"
+            }
+            _ => false,
+        }
+    }
 
-        // If standalone, only use words located between <body> and </body>
-        if !standalone {
-            // If standalone, only prints the body part of the AST
-            words = ast.get_body();
-        } else {
-            // There is a head, so we must insert the scripts in the right place
-            if let Some(i) = ast.begin_head {
-                ast.words.insert(i + 1, Word::Untracked(SCRIPTS.to_owned()));
-            } else {
-                // If there is no head, generate the beginning of the document
-                res.push_str("<html><head>\n");
-                res.push_str("<meta charset = \"UTF-8\">\n");
-                res.push_str(SCRIPTS);
-                res.push_str("</head>\n");
-                if ast.begin_body.is_none() || ast.end_body.is_none() {
-                    res.push_str("<body>\n");
+    // Marks the repeated words of an enumeration as ignored
+    fn flush(ast: &mut Ast, items: &mut Vec<usize>) {
+        if items.len() >= 3 {
+            let mut seen = HashSet::new();
+            for &j in items.iter() {
+                let stemmed = match ast.words[j] {
+                    Word::Tracked(_, ref stemmed, _, _, _) => stemmed.clone(),
+                    _ => continue,
+                };
+                if !seen.insert(stemmed) {
+                    ast.set_ignored(j);
                 }
             }
-            words = &ast.words;
         }
+        items.clear();
+    }
 
-        for word in words {
-            match *word {
-                Word::Untracked(ref s) | Word::Ignored(ref s) => {
-                            if !self.html {
-                                // If input is in text, add <br /> for newlines
-                                let new_str = s.replace("\n", "<br/>\n");
-                                res.push_str(&new_str);
-                            } else {
-                                res.push_str(s);
-                            }
-                },
-                Word::Tracked(ref s, ref stemmed, _, option) => {
-                    let this = format!("<span class = \"{}\" onmouseover = 'on(\"{}\")' \
-                                        onmouseout = 'off(\"{}\")' {}>{}</span>",
-                                       stemmed,
-                                       stemmed,
-                                       stemmed,
-                                       if let Some(colour) = option {
-                                           format!("style = \"text-decoration: underline; color: \
-                                                    {};\"",
-                                                   colour)
-                                       } else {
-                                           String::new()
-                                       },
-                                       s);
-                    res.push_str(&this);
-                }
+    // Indexes of the words of the current enumeration, and whether the last token was a delimiter
+    let mut items: Vec<usize> = vec![];
+    let mut after_delimiter = false;
+    for i in 0..ast.words.len() {
+        if let Word::Untracked(_) = ast.words[i] {
+            if is_delimiter(&ast.words[i]) && !after_delimiter && i > 0 &&
+               items.last() == Some(&(i - 1)) {
+                after_delimiter = true;
+            } else {
+                flush(ast, &mut items);
+                after_delimiter = false;
+            }
+        } else {
+            if !after_delimiter {
+                flush(ast, &mut items);
             }
+            items.push(i);
+            after_delimiter = false;
         }
+    }
+    flush(ast, &mut items);
+}
 
-        if standalone && ast.begin_body.is_none() && ast.end_body.is_none() {
-            // We need to add </body> at the end
-            res.push_str("</body></html>");
+/// Sets the repetition value of each word of a run to the length of the run
+fn set_run_count(words: &mut [Word], indexes: &[usize]) {
+    let v = indexes.len() as f32;
+    for &i in indexes {
+        words[i].set_count_unless_highlighted(v);
+    }
+}
+
+/// Returns the length of the version number, date or identifier at the beginning of `chars`,
+/// if there is one (see `Parser::with_technical_tokens`)
+///
+/// This is a sequence of digits, dots and dashes (beginning and ending with a digit, and
+/// containing at least a dot or a dash), or a letter followed by such a sequence or by digits.
+fn technical_token_len(chars: &[char]) -> Option<usize> {
+    fn is_digit(c: Option<&char>) -> bool {
+        c.is_some_and(|c| c.is_ascii_digit())
+    }
+
+    let letter = chars.len() > 1 && chars[0].is_alphabetic() && is_digit(chars.get(1));
+    let mut i = if letter { 1 } else { 0 };
+    if !is_digit(chars.get(i)) {
+        return None;
+    }
+    let mut separators = 0;
+    while i < chars.len() {
+        if chars[i].is_ascii_digit() {
+            i += 1;
+        } else if (chars[i] == '.' || chars[i] == '-') && is_digit(chars.get(i + 1)) {
+            separators += 1;
+            i += 1;
+        } else {
+            break;
         }
-        res
     }
+    if chars.get(i).is_some_and(|c| c.is_alphabetic()) || !(letter || separators > 0) {
+        None
+    } else {
+        Some(i)
+    }
+}
 
-    /// Stems a string
-    ///
-    /// Either warps call to `stemmer.stem`, or, if `stemmer == None`, just returns the string
-    fn stem(&mut self, s: &str) -> String {
-        match self.stemmer {
-            Some(ref mut stemmer) => stemmer.stem(s),
-            None => s.to_owned(),
+/// Returns the length of the number at the beginning of `chars`, if there is one (see
+/// `Parser::with_track_numbers`)
+///
+/// This is a sequence of digits, possibly separated by dots or commas, that is not followed
+/// by a letter.
+fn number_len(chars: &[char]) -> Option<usize> {
+    let is_digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+    if !is_digit(0) {
+        return None;
+    }
+    let mut i = 0;
+    while is_digit(i) || ((chars[i] == '.' || chars[i] == ',') && is_digit(i + 1)) {
+        i += 1;
+    }
+    if chars.get(i).is_some_and(|c| c.is_alphabetic()) {
+        None
+    } else {
+        Some(i)
+    }
+}
+
+/// Returns a unified diff between two texts, which must have the same number of lines
+fn unified_diff(old: &str, new: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    // Lines, and whether the last one ends with a newline
+    fn lines(s: &str) -> (Vec<&str>, bool) {
+        let mut lines: Vec<&str> = s.split('\n').collect();
+        let eol = s.ends_with('\n') || s.is_empty();
+        if eol {
+            lines.pop();
         }
+        (lines, eol)
     }
 
+    let (old_lines, old_eol) = lines(old);
+    let (new_lines, new_eol) = lines(new);
+    let changed: Vec<usize> = (0..old_lines.len()).filter(|&i| old_lines[i] != new_lines[i]).collect();
+    let mut res = String::new();
+    if changed.is_empty() {
+        return res;
+    }
+    res.push_str("--- original\n+++ suggested\n");
 
-    /// Search a string in a hashmap with fuzzy string matching
-    /// Returns the matching string, or `None`
-    fn fuzzy_get<T>(&self, h: &HashMap<String, T>, pattern: &str) -> String {
-        if let Some(d_max) = self.fuzzy {
-            let length = pattern.len();
-            if length < 2 {
-                // Pattern is too short to do fuzzy matching
-                pattern.to_owned()
+    let mut k = 0;
+    while k < changed.len() {
+        // Changed lines whose context overlap are in the same hunk
+        let mut last = k;
+        while last + 1 < changed.len() && changed[last + 1] - changed[last] <= 2 * CONTEXT {
+            last += 1;
+        }
+        let begin = changed[k].saturating_sub(CONTEXT);
+        let end = (changed[last] + CONTEXT + 1).min(old_lines.len());
+        res.push_str(&format!("@@ -{},{} +{},{} @@\n", begin + 1, end - begin, begin + 1, end - begin));
+        let mut i = begin;
+        while i < end {
+            if old_lines[i] == new_lines[i] {
+                res.push_str(&format!(" {}\n", old_lines[i]));
+                if i + 1 == old_lines.len() && !old_eol {
+                    res.push_str("\\ No newline at end of file\n");
+                }
+                i += 1;
             } else {
-                // If hashmap contains the exact pattern, no need to fuzzy search
-                if h.contains_key(pattern) {
-                    pattern.to_owned()
-                } else {
-                    let mut min_distance = pattern.len() as i32;
-                    let mut key = pattern;
-                    for s in h.keys()
-                              .filter(|s| {
-                                  // string is too small
-                                  if s.len() < 2 {
-                                      return false;
-                                  }
-                                  if (s.len() as f32 - length as f32).abs() >
-                                     (d_max * pattern.len() as f32) {
-                                      // Lengths don't allow a matching distance
-                                      return false;
-                                  }
-                                  true
-                              }) {
-                        let dist = levenshtein(s, pattern) as i32;
-                        if dist < min_distance {
-                            min_distance = dist;
-                            key = s;
-                        }
-                        if min_distance == 1 {
-                            break; // best result since perfect match has been ruled out
+                // Consecutive changed lines are removed, then added
+                let mut j = i;
+                while j < end && old_lines[j] != new_lines[j] {
+                    j += 1;
+                }
+                for &(prefix, lines, eol) in &[('-', &old_lines, old_eol), ('+', &new_lines, new_eol)] {
+                    for l in i..j {
+                        res.push_str(&format!("{}{}\n", prefix, lines[l]));
+                        if l + 1 == lines.len() && !eol {
+                            res.push_str("\\ No newline at end of file\n");
                         }
                     }
-                    if min_distance <= (d_max * pattern.len() as f32) as i32 {
-                        key.to_owned()
-                    } else {
-                        pattern.to_owned()
-                    }
                 }
+                i = j;
             }
-        } else {
-            pattern.to_owned()
         }
+        k = last + 1;
     }
+    res
 }
@@ -0,0 +1,198 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A minimal PDF writer, laying out text with a single font (Helvetica) on A4 pages.
+
+/// Page width, in points (A4)
+const PAGE_WIDTH: f32 = 595.0;
+/// Page height, in points (A4)
+const PAGE_HEIGHT: f32 = 842.0;
+/// Margin on each side of the page
+const MARGIN: f32 = 56.0;
+/// Font size
+const FONT_SIZE: f32 = 11.0;
+/// Space between two lines
+const LEADING: f32 = 15.0;
+
+/// Widths of Helvetica characters from ' ' to '~', in thousandths of the font size
+const HELVETICA_WIDTHS: [u16; 95] = [278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584,
+                                     278, 333, 278, 278, 556, 556, 556, 556, 556, 556, 556, 556,
+                                     556, 556, 278, 278, 584, 584, 584, 556, 1015, 667, 667, 722,
+                                     722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+                                     667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278,
+                                     278, 278, 469, 556, 333, 556, 556, 500, 556, 556, 278, 556,
+                                     556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500,
+                                     278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584];
+
+/// Returns the width of a string, in points
+fn text_width(s: &str) -> f32 {
+    s.chars()
+        .map(|c| {
+            let c = c as usize;
+            if (32..=126).contains(&c) {
+                HELVETICA_WIDTHS[c - 32] as f32
+            } else {
+                556.0
+            }
+        })
+        .sum::<f32>() * FONT_SIZE / 1000.0
+}
+
+/// Escapes a string so it can be used in a PDF string literal
+fn escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                res.push('\\');
+                res.push(c);
+            }
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+/// Lays out words on pages, and outputs them as a PDF file
+pub struct PdfWriter {
+    /// Content streams of the previous pages
+    pages: Vec<String>,
+    /// Content stream of the current page
+    current: String,
+    /// Current horizontal position
+    x: f32,
+    /// Current vertical position (baseline of the current line)
+    y: f32,
+}
+
+impl PdfWriter {
+    /// Creates a new writer, with a first empty page
+    pub fn new() -> PdfWriter {
+        PdfWriter {
+            pages: vec![],
+            current: String::new(),
+            x: MARGIN,
+            y: PAGE_HEIGHT - MARGIN - FONT_SIZE,
+        }
+    }
+
+    /// Writes a word (which shouldn't contain whitespace), underlined in `colour` if there is one
+    ///
+    /// The word goes to the next line if there isn't enough space left on the current one.
+    pub fn write_word(&mut self, s: &str, colour: Option<(f32, f32, f32)>) {
+        let width = text_width(s);
+        if self.x > MARGIN && self.x + width > PAGE_WIDTH - MARGIN {
+            self.new_line();
+        }
+        let (r, g, b) = colour.unwrap_or((0.0, 0.0, 0.0));
+        self.current.push_str(&format!("{:.3} {:.3} {:.3} rg BT /F1 {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                                       r,
+                                       g,
+                                       b,
+                                       FONT_SIZE,
+                                       self.x,
+                                       self.y,
+                                       escape(s)));
+        if colour.is_some() {
+            self.current.push_str(&format!("{:.3} {:.3} {:.3} RG 0.8 w {:.2} {:.2} m {:.2} {:.2} l S\n",
+                                           r,
+                                           g,
+                                           b,
+                                           self.x,
+                                           self.y - 2.0,
+                                           self.x + width,
+                                           self.y - 2.0));
+        }
+        self.x += width;
+    }
+
+    /// Writes a space, unless at the beginning of a line
+    pub fn write_space(&mut self) {
+        if self.x > MARGIN {
+            self.x += text_width(" ");
+        }
+    }
+
+    /// Goes to the next line, and to the next page if the current one is full
+    pub fn new_line(&mut self) {
+        self.x = MARGIN;
+        self.y -= LEADING;
+        if self.y < MARGIN {
+            let page = ::std::mem::take(&mut self.current);
+            self.pages.push(page);
+            self.y = PAGE_HEIGHT - MARGIN - FONT_SIZE;
+        }
+    }
+
+    /// Returns the PDF file
+    pub fn finish(mut self) -> Vec<u8> {
+        let page = ::std::mem::take(&mut self.current);
+        self.pages.push(page);
+
+        // Objects 1, 2 and 3 are the catalog, the page tree and the font; then each page
+        // is followed by its content stream
+        let n_pages = self.pages.len();
+        let kids: Vec<String> = (0..n_pages).map(|i| format!("{} 0 R", 4 + 2 * i)).collect();
+        let mut objects = vec!["<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+                               format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+                                       kids.join(" "),
+                                       n_pages),
+                               "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding \
+                                /WinAnsiEncoding >>"
+                                   .to_owned()];
+        for (i, content) in self.pages.iter().enumerate() {
+            objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources \
+                                  << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+                                 PAGE_WIDTH,
+                                 PAGE_HEIGHT,
+                                 5 + 2 * i));
+            let content = to_latin1(content);
+            objects.push(format!("<< /Length {} >>\nstream\n{}\nendstream",
+                                 content.chars().count(),
+                                 content));
+        }
+
+        let mut res: Vec<u8> = b"%PDF-1.4\n".to_vec();
+        let mut offsets = vec![];
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(res.len());
+            res.extend(format!("{} 0 obj\n", i + 1).bytes());
+            res.extend(latin1_bytes(object));
+            res.extend(b"\nendobj\n".iter());
+        }
+        let xref = res.len();
+        res.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            res.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        res.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                           objects.len() + 1,
+                           xref)
+                       .bytes());
+        res
+    }
+}
+
+/// Replaces characters that can't be encoded in one byte by '?'
+fn to_latin1(s: &str) -> String {
+    s.chars().map(|c| if (c as u32) < 256 { c } else { '?' }).collect()
+}
+
+/// Returns the bytes of a string that only contains characters below 256
+fn latin1_bytes(s: &str) -> Vec<u8> {
+    s.chars().map(|c| if (c as u32) < 256 { c as u8 } else { b'?' }).collect()
+}
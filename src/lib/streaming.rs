@@ -0,0 +1,145 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{HashMap, VecDeque};
+
+use error::Result;
+use parser::Parser;
+use word::Word;
+
+/// A word that has just been repeated too much, as returned by `StreamingDetector::push_line`
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamEvent {
+    /// The stemmed word
+    pub stemmed: String,
+    /// The word, as it appears in the line
+    pub word: String,
+    /// The number of the line (starting at 1)
+    pub line: usize,
+    /// The number of occurrences of the word in the window
+    pub count: u32,
+}
+
+/// Repetition detector for a text that is received line by line (e.g. logs or chat messages).
+///
+/// Contrary to `Parser::detect_local`, which needs the whole text, it only keeps a sliding
+/// window of the last words, whose size is the `max_distance` of the parser. An event is
+/// emitted when the number of occurrences of a word in the window reaches the threshold; it
+/// won't be emitted again for this word until enough occurrences have left the window.
+///
+/// # Example
+///
+/// ```rust
+/// use caribon::{Parser, StreamingDetector};
+/// let parser = Parser::new("english").unwrap().with_max_distance(10);
+/// let mut detector = StreamingDetector::new(parser, 1.9);
+/// assert!(detector.push_line("Connection lost").unwrap().is_empty());
+/// let events = detector.push_line("Connection restored").unwrap();
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(&events[0].word, "Connection");
+/// assert_eq!((events[0].line, events[0].count), (2, 2));
+///
+/// // Still above the threshold, so no new event
+/// assert!(detector.push_line("Connection failed").unwrap().is_empty());
+///
+/// detector.push_line("Everything was fine for a while after that").unwrap();
+/// detector.push_line("Nothing else happened until the evening").unwrap();
+/// // The previous occurrences have left the window, so the word can be detected again
+/// assert!(detector.push_line("Connection failed").unwrap().is_empty());
+/// assert_eq!(detector.push_line("Connection lost").unwrap().len(), 1);
+/// ```
+pub struct StreamingDetector {
+    /// Parser used to tokenize lines
+    parser: Parser,
+    /// Threshold to consider a repetition
+    threshold: f32,
+    /// Max number of words in the window
+    window_size: usize,
+    /// Stems of the words of the window (or `None` for ignored words), oldest first
+    window: VecDeque<Option<String>>,
+    /// Number of occurrences of each stem in the window
+    counts: HashMap<String, u32>,
+    /// Number of lines pushed so far
+    line: usize,
+}
+
+impl StreamingDetector {
+    /// Returns a new `StreamingDetector`.
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` – The parser used to tokenize lines; its max distance is the size of the
+    ///   window.
+    /// * `threshold` – The number of occurrences in the window to consider a repetition
+    ///   (e.g. 1.9).
+    pub fn new(parser: Parser, threshold: f32) -> StreamingDetector {
+        let window_size = parser.max_distance() as usize;
+        StreamingDetector {
+            parser,
+            threshold,
+            window_size,
+            window: VecDeque::new(),
+            counts: HashMap::new(),
+            line: 0,
+        }
+    }
+
+    /// Adds a line to the stream, and returns the words that have just reached the threshold.
+    ///
+    /// Returns `Err(Error)` if the line can't be tokenized.
+    pub fn push_line(&mut self, line: &str) -> Result<Vec<StreamEvent>> {
+        let ast = try!(self.parser.tokenize(line));
+        self.line += 1;
+
+        let mut res = vec![];
+        for word in ast.get_body() {
+            let stemmed = match *word {
+                Word::Untracked(_) => continue,
+                Word::Ignored(_) => None,
+                Word::Tracked(_, ref stemmed, _, _, _) => Some(stemmed),
+            };
+            if self.window.len() >= self.window_size {
+                if let Some(Some(old)) = self.window.pop_front() {
+                    let remove = match self.counts.get_mut(&old) {
+                        Some(count) => {
+                            *count -= 1;
+                            *count == 0
+                        }
+                        None => false,
+                    };
+                    if remove {
+                        self.counts.remove(&old);
+                    }
+                }
+            }
+            if let Some(stemmed) = stemmed {
+                let count = self.counts.entry(stemmed.clone()).or_insert(0);
+                *count += 1;
+                if (*count as f32) >= self.threshold && ((*count - 1) as f32) < self.threshold {
+                    res.push(StreamEvent {
+                        stemmed: stemmed.clone(),
+                        word: word.as_str().to_owned(),
+                        line: self.line,
+                        count: *count,
+                    });
+                }
+            }
+            self.window.push_back(stemmed.cloned());
+        }
+        Ok(res)
+    }
+}
@@ -15,65 +15,172 @@
 // You should have received a copy of the GNU Lesser General Public License
 // along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::borrow::Cow;
+use std::ops::Range;
+
 /// `Word` type: some inner representation used by `Parser`.
 ///
+/// The text of a word is either owned, or borrowed from the input if it was tokenized by
+/// `Parser::tokenize_borrowed`.
+///
 /// You probably should not use this type directly.
 #[repr(C)]
-#[derive(Debug,Clone)]
-pub enum Word {
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub enum Word<'a> {
     /// A String which is not part of the text (typically whitespace, HTML formatting, ...)
-    Untracked(String),
+    Untracked(Cow<'a, str>),
     /// A word that is ignored, either because it is in `parser.ignored` or because it is
-    /// a proper noun and `ignore_proper` has been set to `true`.
-    Ignored(String),
+    /// a proper noun and proper nouns are ignored (see `Parser::with_proper_noun_mode`).
+    Ignored(Cow<'a, str>),
     /// Tracked string, containing the string, the stemmed variant of the
-    /// string, some value corresponding to the degree of repetitions and
-    /// an option to a highlighting colour
-    Tracked(String, String, f32, Option<&'static str>),
+    /// string, some value corresponding to the degree of repetitions,
+    /// an option to a highlighting colour and whether the stemmed variant
+    /// was replaced by another one with fuzzy matching (see `Parser::with_fuzzy`)
+    Tracked(Cow<'a, str>, String, f32, Option<String>, bool),
 }
 
-impl Word {
+impl<'a> Word<'a> {
+    /// Returns the text of a word, as it appears in the input.
+    pub fn as_str(&self) -> &str {
+        match *self {
+            Word::Untracked(ref s) | Word::Ignored(ref s) | Word::Tracked(ref s, _, _, _, _) => s,
+        }
+    }
+
+    /// Returns the stemmed variant of a word, if it is tracked.
+    pub fn stem(&self) -> Option<&str> {
+        match *self {
+            Word::Tracked(_, ref stemmed, _, _, _) => Some(stemmed),
+            _ => None,
+        }
+    }
+
+    /// Returns the repetition value of a word, if it is tracked.
+    pub fn value(&self) -> Option<f32> {
+        match *self {
+            Word::Tracked(_, _, v, _, _) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Returns the highlighting colour of a word, if it is tracked and highlighted.
+    pub fn colour(&self) -> Option<&str> {
+        match *self {
+            Word::Tracked(_, _, _, Some(ref colour), _) => Some(colour),
+            _ => None,
+        }
+    }
+
+    /// Returns true if the word is tracked.
+    pub fn is_tracked(&self) -> bool {
+        matches!(*self, Word::Tracked(..))
+    }
+
     /// Sets the stemmed value of a word.
     pub fn set_stemmed(&mut self, s: String) {
-        if let Word::Tracked(_, ref mut stemmed, _, _) = *self {
+        if let Word::Tracked(_, ref mut stemmed, _, _, _) = *self {
             *stemmed = s;
         }
     }
 
     /// Sets the repetition value of a word.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("A word, another word").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// let value = ast.words[2].value();
+    /// assert!(ast.words[2].colour().is_some());
+    ///
+    /// // Highlighted words keep the value that caused their highlighting...
+    /// parser.detect_global(&mut ast, 0.9);
+    /// assert_eq!(ast.words[2].value(), value);
+    ///
+    /// // ...but it can still be changed
+    /// ast.words[2].set_count(0.5);
+    /// assert_eq!(ast.words[2].value(), Some(0.5));
+    /// ```
     pub fn set_count(&mut self, x: f32) {
-        if let Word::Tracked(_, _, ref mut v, _) = *self {
+        if let Word::Tracked(_, _, ref mut v, _, _) = *self {
             *v = x;
         }
     }
+
+    /// Sets the repetition value of a word that isn't highlighted yet, so highlighted words
+    /// keep the value that caused their highlighting when another algorithm is run.
+    pub(crate) fn set_count_unless_highlighted(&mut self, x: f32) {
+        if let Word::Tracked(_, _, ref mut v, None, _) = *self {
+            *v = x;
+        }
+    }
+
+    /// Returns a word owning its text, so it doesn't borrow the input anymore.
+    pub fn into_owned(self) -> Word<'static> {
+        match self {
+            Word::Untracked(s) => Word::Untracked(Cow::Owned(s.into_owned())),
+            Word::Ignored(s) => Word::Ignored(Cow::Owned(s.into_owned())),
+            Word::Tracked(s, stemmed, v, colour, fuzzy) => {
+                Word::Tracked(Cow::Owned(s.into_owned()), stemmed, v, colour, fuzzy)
+            }
+        }
+    }
 }
 
 #[repr(C)]
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
 /// The internal representation of the document.
 ///
 /// Technically the name AST is not really well chosen, since it is not a tree, but mainly a vector of
 /// `Word`s plus some additonal informations for HTML parsing, but the idea is that it is the internal
 /// representation of the documentation.
-pub struct Ast {
+///
+/// It can be serialized (e.g. with `serde_json`), so a text only needs to be tokenized once to
+/// try different detection methods or thresholds.
+///
+/// # Example
+///
+/// ```rust
+/// extern crate caribon;
+/// extern crate serde_json;
+/// # fn main() {
+/// let mut parser = caribon::Parser::new("english").unwrap();
+/// let mut ast = parser.tokenize("This is a <b>text</b>, a short text.").unwrap();
+/// parser.detect_local(&mut ast, 1.9);
+///
+/// let json = serde_json::to_string(&ast).unwrap();
+/// let restored: caribon::Ast = serde_json::from_str(&json).unwrap();
+/// assert_eq!(restored.words, ast.words);
+/// assert_eq!(restored, ast);
+/// # }
+/// ```
+pub struct Ast<'a> {
     /// Vector of `Word`s. The main data of the structure.
-    pub words: Vec<Word>,
+    pub words: Vec<Word<'a>>,
     /// Position of <head> tag, if any
     pub begin_head: Option<usize>,
     /// position of <body> tag, if any
     pub begin_body: Option<usize>,
     /// position of </body> tag, if any
     pub end_body: Option<usize>,
+    /// positions of the words that begin a sentence (except the first one)
+    pub(crate) sentences: Vec<usize>,
+    /// positions of the markers beginning each file and names of the files, if the Ast was
+    /// created by `Parser::tokenize_many`
+    pub(crate) files: Vec<(usize, String)>,
 }
 
-impl Ast {
+impl<'a> Ast<'a> {
     /// Creates a new, empty AST
-    pub fn new() -> Ast {
+    pub fn new() -> Ast<'a> {
         Ast {
             words: vec![],
             begin_head: None,
             begin_body: None,
             end_body: None,
+            sentences: vec![],
+            files: vec![],
         }
     }
 
@@ -109,32 +216,252 @@ impl Ast {
         self.end_body = Some(i);
     }
 
-    /// Get only the words contained between <body> and </body>
+    /// Marks the beginning of a new sentence at current last position of words
+    ///
+    /// This should be called *before* inserting the corresponding element.
+    pub fn mark_sentence(&mut self) {
+        let i = self.words.len();
+        self.sentences.push(i);
+    }
+
+    /// Marks the beginning of a new file at current last position of words
+    ///
+    /// This should be called *before* inserting the corresponding marker.
+    pub fn mark_file(&mut self, name: &str) {
+        let i = self.words.len();
+        self.files.push((i, name.to_owned()));
+    }
+
+    /// Inserts a word at position `i`, updating the positions of the body, sentences and files
+    pub(crate) fn insert_word(&mut self, i: usize, word: Word<'a>) {
+        self.words.insert(i, word);
+        let positions = self.begin_head
+            .iter_mut()
+            .chain(self.begin_body.iter_mut())
+            .chain(self.end_body.iter_mut())
+            .chain(self.sentences.iter_mut())
+            .chain(self.files.iter_mut().map(|(p, _)| p));
+        for p in positions {
+            if *p >= i {
+                *p += 1;
+            }
+        }
+    }
+
+    /// Returns the positions of the words that begin a sentence (except the first one)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("One. Two").unwrap();
+    /// assert_eq!(ast.sentences(), &[2]);
+    /// ```
+    pub fn sentences(&self) -> &[usize] {
+        &self.sentences
+    }
+
+    /// Returns the positions of the markers beginning each file and the names of the files,
+    /// if the Ast was created by `Parser::tokenize_many`
+    pub fn files(&self) -> &[(usize, String)] {
+        &self.files
+    }
+
+    /// Returns the name of the file the word at position `i` comes from, if the Ast was
+    /// created by `Parser::tokenize_many`
+    pub fn file_at(&self, i: usize) -> Option<&str> {
+        self.files
+            .iter()
+            .take_while(|(j, _)| *j <= i)
+            .last()
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Returns an iterator over the tracked words.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(ast.tracked().count(), 7);
+    /// let total: f32 = ast.tracked().filter_map(|word| word.value()).sum();
+    /// assert_eq!(total, 4.0);
+    /// let stems: Vec<_> = ast.tracked().filter(|w| w.colour().is_some()).map(|w| w.stem()).collect();
+    /// assert_eq!(stems, vec![Some("word"), Some("word")]);
+    /// ```
+    pub fn tracked(&self) -> impl Iterator<Item = &Word<'a>> {
+        self.words.iter().filter(|word| word.is_tracked())
+    }
+
+    /// Returns an iterator over the tracked words, allowing to modify them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("One word, two words").unwrap();
+    /// for word in ast.tracked_mut() {
+    ///     word.set_count(1.0);
+    /// }
+    /// assert!(ast.tracked().all(|word| word.value() == Some(1.0)));
+    /// ```
+    pub fn tracked_mut(&mut self) -> impl Iterator<Item = &mut Word<'a>> {
+        self.words.iter_mut().filter(|word| word.is_tracked())
+    }
+
+    /// Returns, for each word, the number of the sentence it belongs to (starting at 0)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = parser.tokenize("One. Two").unwrap();
+    /// assert_eq!(ast.sentence_numbers(), vec![0, 0, 1]);
+    /// ```
+    pub fn sentence_numbers(&self) -> Vec<u32> {
+        let mut res = Vec::with_capacity(self.words.len());
+        let mut n = 0;
+        let mut next = self.sentences.iter().peekable();
+        for i in 0..self.words.len() {
+            while next.peek().is_some_and(|&&j| j <= i) {
+                next.next();
+                n += 1;
+            }
+            res.push(n);
+        }
+        res
+    }
+
+    /// Returns the range of words contained between <body> and </body>
     ///
     /// If body_begin and body_end are both set (and the first one is before the second),
-    /// returns a slice that contains only words in this part; else, returns all words.
-    pub fn get_body(&self) -> &[Word] {
+    /// returns the range of words in this part; else, returns the range of all words.
+    pub fn body_range(&self) -> Range<usize> {
         if let Some(begin) = self.begin_body {
             if let Some(end) = self.end_body {
                 if begin < end {
-                    return &self.words[begin + 1..end];
+                    return begin + 1..end;
                 }
             }
         }
-        &self.words
+        0..self.words.len()
     }
 
-    /// Get only the words contained between <body> and </body>, mutable version
+    /// Marks the word at `index` as ignored, so it won't be counted by further detections.
     ///
-    /// Same as get_body, but takes and return a mutable reference.
-    pub fn get_body_mut(&mut self) -> &mut [Word] {
-        if let Some(begin) = self.begin_body {
-            if let Some(end) = self.end_body {
-                if begin < end {
-                    return &mut self.words[begin + 1..end];
+    /// Only `Tracked` words are converted to `Ignored` (keeping their text); other words
+    /// are left untouched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caribon::Parser;
+    /// let mut parser = Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    ///
+    /// // Dismiss the repetition: unhighlight "word" and ignore "words"
+    /// let last = ast.words.len() - 1;
+    /// ast.clear_highlight_at(2);
+    /// ast.set_ignored(last);
+    ///
+    /// let mut parser = Parser::new("english").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// assert_eq!(&parser.ast_to_markdown(&ast), "This word is repeated in a few words");
+    /// ```
+    pub fn set_ignored(&mut self, index: usize) {
+        let s = match self.words[index] {
+            Word::Tracked(ref s, _, _, _, _) => s.clone(),
+            _ => return,
+        };
+        self.words[index] = Word::Ignored(s);
+    }
+
+    /// Removes the highlighting of the word at `index`, if any.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn clear_highlight_at(&mut self, index: usize) {
+        if let Word::Tracked(_, _, ref mut v, ref mut option, _) = self.words[index] {
+            *v = 0.0;
+            *option = None;
+        }
+    }
+
+    /// Returns the longest range of words that doesn't contain any highlighted word.
+    ///
+    /// The range is returned as `(begin, end)`, `end` being excluded; if there are several
+    /// ranges of the same length, the first one is returned.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let mut ast = parser.tokenize("This word is repeated in a few words").unwrap();
+    /// parser.detect_local(&mut ast, 1.9);
+    /// // "word" is at index 2 and "words" at index 14
+    /// assert_eq!(ast.longest_clean_run(), (3, 14));
+    /// ```
+    pub fn longest_clean_run(&self) -> (usize, usize) {
+        let mut res = (0, 0);
+        let mut begin = 0;
+        for (i, word) in self.words.iter().enumerate() {
+            if let Word::Tracked(_, _, _, Some(_), _) = *word {
+                if i - begin > res.1 - res.0 {
+                    res = (begin, i);
                 }
+                begin = i + 1;
             }
         }
-        &mut self.words
+        if self.words.len() - begin > res.1 - res.0 {
+            res = (begin, self.words.len());
+        }
+        res
+    }
+
+    /// Get only the words contained between <body> and </body>
+    ///
+    /// If body_begin and body_end are both set (and the first one is before the second),
+    /// returns a slice that contains only words in this part; else, returns all words.
+    pub fn get_body(&self) -> &[Word<'a>] {
+        &self.words[self.body_range()]
+    }
+
+    /// Get only the words contained between <body> and </body>, mutable version
+    ///
+    /// Same as get_body, but takes and return a mutable reference.
+    pub fn get_body_mut(&mut self) -> &mut [Word<'a>] {
+        let range = self.body_range();
+        &mut self.words[range]
+    }
+
+    /// Returns an Ast owning the text of its words, so it doesn't borrow the input anymore.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut parser = caribon::Parser::new("english").unwrap();
+    /// let ast = {
+    ///     let text = String::from("Some words, more words");
+    ///     parser.tokenize_borrowed(&text).unwrap().into_owned()
+    /// };
+    /// assert_eq!(ast, parser.tokenize("Some words, more words").unwrap());
+    /// ```
+    pub fn into_owned(self) -> Ast<'static> {
+        Ast {
+            words: self.words.into_iter().map(Word::into_owned).collect(),
+            begin_head: self.begin_head,
+            begin_body: self.begin_body,
+            end_body: self.end_body,
+            sentences: self.sentences,
+            files: self.files,
+        }
     }
 }
@@ -0,0 +1,75 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Measures the memory used by `tokenize_borrowed` compared to `tokenize`.
+
+extern crate caribon;
+
+use caribon::Parser;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// An allocator keeping track of the peak of allocated memory
+struct Counter;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counter {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counter = Counter;
+
+/// Returns the result of `f` and the peak of memory allocated while running it
+fn peak_memory<T, F: FnOnce() -> T>(f: F) -> (T, usize) {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    let res = f();
+    (res, PEAK.load(Ordering::SeqCst) - before)
+}
+
+#[test]
+fn borrowed_uses_less_memory() {
+    let text = format!("<html><body>{}</body></html>",
+                       "<p>Élisabeth wrote some words, and then some more words.</p>\n"
+                           .repeat(20000));
+    let mut parser = Parser::new("english").unwrap();
+
+    let (owned, owned_peak) = peak_memory(|| parser.tokenize(&text).unwrap());
+    let (borrowed, borrowed_peak) = peak_memory(|| parser.tokenize_borrowed(&text).unwrap());
+    assert_eq!(borrowed, owned);
+    // The owned Ast has a copy of the text, and tokenize holds all its characters
+    assert!(borrowed_peak + text.len() < owned_peak,
+            "tokenize_borrowed used {} bytes, tokenize used {} bytes",
+            borrowed_peak,
+            owned_peak);
+}
@@ -0,0 +1,75 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Runs the `caribon` program on a text, from tokenizing to displaying the repetitions.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+
+/// Runs caribon with some arguments, giving it `input` on stdin, and returns its output
+fn caribon(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_caribon"))
+                        .args(args)
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .spawn()
+                        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn cli_detects_repetitions_in_text() {
+    let output = caribon(&["--language=english", "--output-format=markdown"],
+                         "This word is repeated in a few words");
+    assert_eq!(output, "This **word** is repeated in a few **words**");
+}
+
+#[test]
+fn cli_outputs_html() {
+    let output = caribon(&["--language=english", "--output-format=html"],
+                         "This word is repeated in a few words");
+    assert!(output.starts_with("<html>"));
+    assert!(output.contains("color: green;\">word</span>"));
+    assert!(output.contains("color: green;\">words</span>"));
+    assert!(output.contains(" >repeated</span>"));
+}
+
+#[test]
+fn cli_reads_and_writes_files() {
+    let dir = env::temp_dir();
+    let input = dir.join(format!("caribon-cli-{}.html", std::process::id()));
+    let output = dir.join(format!("caribon-cli-{}.md", std::process::id()));
+    File::create(&input)
+        .unwrap()
+        .write_all(b"<html><body><p>One cat, <em>two</em> cats.</p></body></html>")
+        .unwrap();
+    caribon(&["--language=english",
+              &format!("--input={}", input.display()),
+              &format!("--output={}", output.display())],
+            "");
+    let mut result = String::new();
+    File::open(&output).unwrap().read_to_string(&mut result).unwrap();
+    fs::remove_file(&input).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert_eq!(result, "<html><body><p>One **cat**, <em>two</em> **cats**.</p></body></html>");
+}
@@ -0,0 +1,104 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks that fuzzy matching, which only compares stems whose lengths allow a match,
+//! gives the same clusters as a serial scan of all stems.
+
+extern crate caribon;
+
+use caribon::{Parser, WordKind};
+
+/// Generates a text of `n` words, where words are often a letter away from each other
+fn synthetic_text(n: usize) -> String {
+    // Simple linear congruential generator, so the text is always the same
+    let mut seed: u64 = 42;
+    let mut next = move |max: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((seed >> 33) % max as u64) as usize
+    };
+    let letters: Vec<char> = "abcdefghijklmnopqrstuvwxyz".chars().collect();
+    let mut vocabulary: Vec<String> = vec![];
+    while vocabulary.len() < 800 {
+        let word = if !vocabulary.is_empty() && next(2) == 0 {
+            // Variant of an existing word, with a letter changed, added or removed
+            let mut chars: Vec<char> = vocabulary[next(vocabulary.len())].chars().collect();
+            let i = next(chars.len());
+            match next(3) {
+                0 => chars[i] = letters[next(letters.len())],
+                1 => chars.insert(i, letters[next(letters.len())]),
+                _ if chars.len() > 2 => {
+                    chars.remove(i);
+                }
+                _ => (),
+            }
+            chars.into_iter().collect()
+        } else {
+            let len = 2 + next(9);
+            (0..len).map(|_| letters[next(letters.len())]).collect()
+        };
+        vocabulary.push(word);
+    }
+    (0..n).map(|_| vocabulary[next(vocabulary.len())].as_str()).collect::<Vec<_>>().join(" ")
+}
+
+/// Reference implementation: compares each new stem with all the stems kept so far, and
+/// merges it with the closest one (the shortest, then the first in alphabetical order, in
+/// case of a tie), if it is close enough
+fn serial_merges(stems: &[String], fuzzy: f32) -> Vec<(String, String)> {
+    let mut keys: Vec<String> = vec![];
+    let mut seen: Vec<&str> = vec![];
+    let mut res = vec![];
+    for stem in stems {
+        if seen.contains(&stem.as_str()) {
+            continue;
+        }
+        seen.push(stem);
+        let length = stem.chars().count();
+        let limit = (fuzzy * length as f32) as usize;
+        let best = if length < 2 || keys.contains(stem) {
+            None
+        } else {
+            keys.iter()
+                .filter(|key| key.chars().count() >= 2)
+                .map(|key| (caribon::edit_distance(key, stem), key.chars().count(), key))
+                .filter(|&(distance, _, _)| distance <= limit)
+                .min()
+        };
+        match best {
+            Some((_, _, key)) => res.push((key.clone(), stem.clone())),
+            None => keys.push(stem.clone()),
+        }
+    }
+    res
+}
+
+#[test]
+fn fuzzy_index_matches_serial_scan() {
+    let text = synthetic_text(10000);
+    let mut parser = Parser::new("english").unwrap().with_html(false);
+    let ast = parser.tokenize(&text).unwrap();
+    let stems: Vec<String> = parser.tokens(&ast)
+                                   .into_iter()
+                                   .filter(|token| token.kind == WordKind::Tracked)
+                                   .map(|token| token.stem.unwrap())
+                                   .collect();
+    for &fuzzy in &[0.2, 0.35, 0.5] {
+        let expected = serial_merges(&stems, fuzzy);
+        assert!(!expected.is_empty());
+        assert_eq!(parser.fuzzy_diff(&text, fuzzy).unwrap(), expected);
+    }
+}
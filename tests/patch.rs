@@ -0,0 +1,92 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Checks that the patches returned by `suggest_patch` apply to the source text.
+
+extern crate caribon;
+
+use caribon::Parser;
+
+/// Applies a unified diff to a text, checking that context and removed lines match
+fn apply(source: &str, patch: &str) -> String {
+    let old: Vec<&str> = source.lines().collect();
+    let mut res: Vec<String> = vec![];
+    let mut i = 0;
+    let mut lines = patch.lines();
+    assert_eq!(lines.next(), Some("--- original"));
+    assert_eq!(lines.next(), Some("+++ suggested"));
+    for line in lines {
+        if let Some(header) = line.strip_prefix("@@ -") {
+            let begin: usize = header.split(',').next().unwrap().parse().unwrap();
+            while i + 1 < begin {
+                res.push(old[i].to_owned());
+                i += 1;
+            }
+        } else if let Some(context) = line.strip_prefix(' ') {
+            assert_eq!(context, old[i]);
+            res.push(old[i].to_owned());
+            i += 1;
+        } else if let Some(removed) = line.strip_prefix('-') {
+            assert_eq!(removed, old[i]);
+            i += 1;
+        } else if let Some(added) = line.strip_prefix('+') {
+            res.push(added.to_owned());
+        } else {
+            panic!("unexpected line in patch: {:?}", line);
+        }
+    }
+    res.extend(old[i..].iter().map(|s| s.to_string()));
+    res.join("\n") + "\n"
+}
+
+#[test]
+fn patch_applies_to_source() {
+    let source = "The cat sleeps on the mat.\n\
+                  Nothing happens.\n\
+                  Nothing happens again.\n\
+                  Still nothing.\n\
+                  Really nothing at all.\n\
+                  Nobody moves.\n\
+                  Nobody talks.\n\
+                  Nobody laughs.\n\
+                  Nobody cries.\n\
+                  The cat wakes up, and the cat leaves.\n";
+    let mut parser = Parser::new("english")
+                         .unwrap()
+                         .with_html(false)
+                         .with_synonyms(|word| match word {
+                             "cat" => vec!["feline\nanimal".to_owned(), "feline".to_owned()],
+                             _ => vec![],
+                         });
+    let mut ast = parser.tokenize(source).unwrap();
+    parser.detect_local(&mut ast, 1.9);
+    let patched = apply(source, &parser.suggest_patch(&ast, source));
+    assert_eq!(patched.lines().count(), source.lines().count());
+    assert!(patched.contains("\nThe feline wakes up, and the feline leaves.\n"));
+    assert!(patched.contains("\nNobody moves.\n<REPHRASE> talks.\n"));
+}
+
+#[test]
+fn patch_keeps_first_occurrence_of_each_chain() {
+    let source = "one cat, two cat,\nthree four five six seven eight\nnine cat, ten cat\n";
+    let mut parser = Parser::new("english").unwrap().with_html(false).with_max_distance(4);
+    let mut ast = parser.tokenize(source).unwrap();
+    parser.detect_local(&mut ast, 1.9);
+    assert_eq!(apply(source, &parser.suggest_patch(&ast, source)),
+               "one cat, two <REPHRASE>,\nthree four five six seven eight\nnine cat, ten <REPHRASE>\n");
+}
@@ -0,0 +1,120 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Checks that `tokenize_reader` gives the same Ast as `tokenize` when the input is read in
+//! chunks splitting words, tags and characters.
+
+extern crate caribon;
+
+use caribon::{Parser, ProperNounMode, Word, WordKind};
+use std::cmp;
+use std::io::{self, Read};
+
+/// A reader returning its content in chunks, ending at the given byte positions
+struct Chunks<'a> {
+    content: &'a [u8],
+    pos: usize,
+    splits: Vec<usize>,
+}
+
+impl<'a> Chunks<'a> {
+    fn new(content: &'a str, splits: Vec<usize>) -> Chunks<'a> {
+        Chunks {
+            content: content.as_bytes(),
+            pos: 0,
+            splits,
+        }
+    }
+
+    /// Splits the content every `size` bytes
+    fn every(content: &'a str, size: usize) -> Chunks<'a> {
+        Chunks::new(content, (1..content.len() / size + 1).map(|i| i * size).collect())
+    }
+}
+
+impl<'a> Read for Chunks<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let end = self.splits
+                      .iter()
+                      .cloned()
+                      .find(|&split| split > self.pos)
+                      .unwrap_or(self.content.len());
+        let n = cmp::min(end - self.pos, buf.len());
+        buf[..n].copy_from_slice(&self.content[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[test]
+fn split_inside_word_and_tag() {
+    let filler = "Some words are written here, and some words are repeated. ".repeat(10);
+    let long_tag = format!("<p title = \"{}\">", "a long title ".repeat(30));
+    let text = format!("{}{}Una col·lecció de llibres.</p>{}", filler, long_tag, filler);
+    // Inside the tag, inside the word and inside the (two bytes) middle dot
+    let tag = text.find("<p").unwrap();
+    let word = text.find("col·").unwrap();
+    let splits = vec![tag + 2, tag + 200, word + 2, word + 4, word + 6];
+
+    let mut parser = Parser::new("catalan").unwrap();
+    let ast = parser.tokenize_reader(Chunks::new(&text, splits)).unwrap();
+    assert_eq!(ast, parser.tokenize(&text).unwrap());
+    assert!(ast.words.contains(&Word::Untracked(long_tag.clone().into())));
+    assert!(ast.tracked().any(|word| word.as_str() == "col·lecció"));
+}
+
+#[test]
+fn small_chunks() {
+    let text = "<html><head><title>Un été</title></head><body>\n\
+                <p>Dr. Smith wrote version 1.2.3 of the program in 2017, and the 3.14 \
+                release fixed it. Dr. Smith's sœur said: «&nbsp;C'est l'été&nbsp;!»</p>\n\
+                <pre>Code is not tracked, code is not tracked</pre>\n\
+                <p>An exam\u{00AD}ple of a long para-\n  graph, with NASA and Nasa.</p>\n\
+                <![CDATA[Some <raw> data]]>\n"
+        .repeat(20) + "</body></html>";
+    let mut parser = Parser::new("english")
+                         .unwrap()
+                         .with_technical_tokens(Some(WordKind::Ignored))
+                         .with_track_numbers(true)
+                         .with_dehyphenate(true)
+                         .with_proper_noun_mode(ProperNounMode::IgnoreCapitalizedExceptAcronyms);
+    let expected = parser.tokenize(&text).unwrap();
+    for &size in &[1, 3, 7, 100, 10000] {
+        let ast = parser.tokenize_reader(Chunks::every(&text, size)).unwrap();
+        assert_eq!(ast, expected, "chunks of {} bytes", size);
+    }
+
+    let mut parser = Parser::new("english").unwrap().with_html(false);
+    let text = text.replace(['<', '>'], " ");
+    let expected = parser.tokenize(&text).unwrap();
+    let ast = parser.tokenize_reader(Chunks::every(&text, 5)).unwrap();
+    assert_eq!(ast, expected);
+}
+
+#[test]
+fn errors() {
+    let filler = "Some text. ".repeat(100);
+    let mut parser = Parser::new("english").unwrap();
+    for text in &[format!("{}<p>Unclosed <b {}", filler, filler),
+                  format!("{}Fish &amp chips", filler)] {
+        let expected = parser.tokenize(text).unwrap_err();
+        let err = parser.tokenize_reader(Chunks::every(text, 7)).unwrap_err();
+        assert_eq!(err.position(), expected.position());
+        assert_eq!(err.to_string(), expected.to_string());
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright (C) 2015 Élisabeth HENRY.
+//
+// This file is part of Caribon.
+//
+// Caribon is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Lesser General Public License as published
+// by the Free Software Foundation, either version 2.1 of the License, or
+// (at your option) any later version.
+//
+// Caribon is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Lesser General Public License for more details.
+//
+// You should have received a copy of the GNU Lesser General Public License
+// along with Caribon.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Checks that the output of `ast_to_xml` is well-formed XML.
+
+extern crate caribon;
+extern crate roxmltree;
+
+use caribon::Parser;
+
+/// Parses the XML output and returns the text, and the (text, stem) of each `<w>` element
+fn parse(xml: &str) -> (String, Vec<(String, String)>) {
+    let doc = roxmltree::Document::parse(xml).expect("ast_to_xml output is not well-formed");
+    let root = doc.root_element();
+    assert_eq!(root.tag_name().name(), "text");
+    let text: String = root.descendants().filter(|n| n.is_text()).filter_map(|n| n.text()).collect();
+    let words = root.children()
+                    .filter(|n| n.is_element())
+                    .map(|n| {
+                        assert_eq!(n.tag_name().name(), "w");
+                        assert!(n.attribute("rep").unwrap().parse::<f32>().is_ok());
+                        (n.text().unwrap().to_owned(), n.attribute("stem").unwrap().to_owned())
+                    })
+                    .collect();
+    (text, words)
+}
+
+#[test]
+fn xml_from_text_is_well_formed() {
+    let input = "Fish & chips <3, \"fish\" & 'chips' > <w>fish</w>]]> chips";
+    let mut parser = Parser::new("english").unwrap().with_html(false);
+    let mut ast = parser.tokenize(input).unwrap();
+    parser.detect_local(&mut ast, 1.9);
+    let (text, words) = parse(&parser.ast_to_xml(&ast));
+    assert_eq!(text, input);
+    assert_eq!(words[0], ("Fish".to_owned(), "fish".to_owned()));
+    assert!(words.iter().any(|w| w.0 == "w"));
+}
+
+#[test]
+fn xml_from_html_skips_tags() {
+    let input = "<html><head><title>Fish</title></head>\
+                 <body><p>Fish &amp; <b class=\"x\">chips</b>, fish &amp; chips<br/></p></body></html>";
+    let mut parser = Parser::new("english").unwrap();
+    let mut ast = parser.tokenize(input).unwrap();
+    parser.detect_local(&mut ast, 1.9);
+    let (text, words) = parse(&parser.ast_to_xml(&ast));
+    assert_eq!(text, "Fish &amp; chips, fish &amp; chips");
+    assert_eq!(words.len(), 4);
+}